@@ -0,0 +1,141 @@
+use crate::parse::Activity;
+use chrono::DateTime;
+use serde_json::Value;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// A rule maps an event to a major.minor.activity tag when the given key of the
+// event's data (e.g. "app" or "title") contains the pattern. The key "*"
+// matches every event and is useful as a catch-all at the end of the file.
+pub struct Rule {
+    key: String,
+    pattern: String,
+    tag: String,
+}
+
+impl Rule {
+    fn matches(&self, data: &Value) -> bool {
+        if self.key == "*" {
+            return true;
+        }
+
+        match data.get(&self.key).and_then(|x| x.as_str()) {
+            Some(x) => x.to_lowercase().contains(&self.pattern),
+            None => false,
+        }
+    }
+}
+
+// Rules are read one per line as "<key> <tag> <pattern>", where the pattern is
+// the rest of the line and is matched case-insensitively. The first matching
+// rule wins and events that match no rule are dropped. Without the file every
+// event is dropped.
+pub fn parse_rules(filename: &str) -> Vec<Rule> {
+    if !Path::new(filename).exists() {
+        return Vec::new();
+    }
+
+    let mut file = File::open(filename).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+
+    let mut rules = Vec::new();
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let mut split = line.splitn(3, ' ');
+        let key = split.next().unwrap().to_string();
+        let tag = split.next().unwrap().to_string();
+        let pattern = split.next().unwrap_or("").to_lowercase();
+
+        if tag.split('.').count() != 3 {
+            panic!("Invalid ActivityWatch rule: {line}");
+        }
+
+        rules.push(Rule { key, pattern, tag });
+    }
+
+    rules
+}
+
+// Accepts a bucket export from the web UI ({"buckets": {...}}), a single bucket
+// ({"events": [...]}) or a bare list of events as returned by the REST API. Of a
+// full export only the window buckets are read, since the AFK and browser
+// watchers cover the same minutes again.
+fn collect_events(json: &Value) -> Vec<&Value> {
+    if let Some(events) = json.as_array() {
+        return events.iter().collect();
+    }
+
+    if let Some(events) = json.get("events").and_then(|x| x.as_array()) {
+        return events.iter().collect();
+    }
+
+    let mut events = Vec::new();
+    if let Some(buckets) = json.get("buckets").and_then(|x| x.as_object()) {
+        for bucket in buckets.values() {
+            if bucket.get("type").and_then(|x| x.as_str()) == Some("currentwindow") {
+                events.extend(collect_events(bucket));
+            }
+        }
+    }
+
+    events
+}
+
+pub fn parse_export(
+    filename: &str,
+    rules: &[Rule],
+    begin_timestamp: u64,
+    end_timestamp: u64,
+) -> Vec<Activity> {
+    let mut file = File::open(filename).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+    let json: Value = serde_json::from_str(&contents).unwrap();
+
+    let mut activities = Vec::new();
+    for event in collect_events(&json) {
+        let timestamp = event["timestamp"].as_str().unwrap();
+        let duration = event["duration"].as_f64().unwrap();
+        let data = &event["data"];
+
+        // AFK watcher events only say whether someone was at the computer.
+        if data.get("status").is_some() {
+            continue;
+        }
+
+        let tag = match rules.iter().find(|rule| rule.matches(data)) {
+            Some(rule) => rule.tag.clone(),
+            None => continue,
+        };
+
+        let mut start_time = DateTime::parse_from_rfc3339(timestamp).unwrap().timestamp() as u64;
+        let mut end_time = start_time + duration as u64;
+
+        if start_time < begin_timestamp {
+            start_time = begin_timestamp;
+        }
+
+        if end_time > end_timestamp {
+            end_time = end_timestamp;
+        }
+
+        let delta = end_time as i64 - start_time as i64;
+
+        if delta > 0 {
+            activities.push((start_time, delta, tag));
+        }
+    }
+
+    activities.sort_by_key(|x| x.0);
+
+    activities
+}
@@ -0,0 +1,69 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// Number of sessions renamed by each alias and the time they cover, indexed
+// like the alias list.
+pub type AliasMatches = Vec<(u64, i64)>;
+
+pub struct Alias {
+    pub from: String,
+    pub to: String,
+}
+
+// The alias file holds one "<old> <new>" pair per line. The old side is either
+// a full tag or a prefix such as "work" or "work.code", and only the matched
+// prefix is replaced, so "work.code dev.code" turns "work.code.rust" into
+// "dev.code.rust". Both sides must have the same depth.
+pub fn parse_aliases(filename: &str) -> Vec<Alias> {
+    if !Path::new(filename).exists() {
+        return Vec::new();
+    }
+
+    let mut file = File::open(filename).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+
+    let mut aliases = Vec::new();
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let mut split = line.split(' ');
+        match (split.next(), split.next(), split.next()) {
+            (Some(from), Some(to), None) if from.split('.').count() != to.split('.').count() => {
+                panic!("Alias changes the depth of the tag: {line}")
+            }
+            (Some(from), Some(to), None) => aliases.push(Alias {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            _ => panic!("Invalid alias file"),
+        }
+    }
+
+    aliases
+}
+
+// Returns the renamed tag and the index of the alias that was applied. When
+// several aliases match, the longest prefix wins.
+pub fn apply_aliases(tag: &str, aliases: &[Alias]) -> (String, Option<usize>) {
+    let matched = aliases
+        .iter()
+        .enumerate()
+        .filter(|(_, alias)| {
+            tag == alias.from
+                || (tag.starts_with(&alias.from) && tag[alias.from.len()..].starts_with('.'))
+        })
+        .max_by_key(|(_, alias)| alias.from.len());
+
+    match matched {
+        Some((index, alias)) => (alias.to.clone() + &tag[alias.from.len()..], Some(index)),
+        None => (tag.to_string(), None),
+    }
+}
@@ -0,0 +1,114 @@
+use crate::sources::load_activities;
+use crate::svg::{group, rect, svg, text};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::util::{escape, format_time};
+use std::collections::HashMap;
+
+// How far either side of the ideal still counts as on target, as a fraction of
+// the ideal.
+const TOLERANCE: f64 = 0.25;
+
+// One bullet per category of the ideal: a band for the target range, a bar for
+// the actual share and a tick at the ideal. Shares are taken of the time spent
+// in those categories, and bars are colored like the ratios in the stats
+// table.
+pub fn render_bullet(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    ideal_proportions: &HashMap<String, f64>,
+    width: f64,
+    tags: &Registry,
+    theme: &Theme,
+) -> String {
+    let (tree, _, _) = load_activities(start_timestamp, end_timestamp);
+
+    let mut keys: Vec<&String> = ideal_proportions
+        .keys()
+        .filter(|x| x.as_str() != "slop")
+        .collect();
+    keys.sort();
+
+    let time_domain = keys.iter().fold(0., |acc, x| tree.value_at(x) + acc);
+    let share = |key: &str| match time_domain > 0. {
+        true => 100. * tree.value_at(key) / time_domain,
+        false => 0.,
+    };
+
+    let largest = keys.iter().fold(0., |acc: f64, x| {
+        acc.max(share(x))
+            .max(ideal_proportions[*x] * (1. + TOLERANCE))
+    });
+    let axis = (largest / 10.).ceil().max(1.) * 10.;
+
+    let left = 100.;
+    let top = 10.;
+    let row_height = 30.;
+    let plot_width = width - left - 20.;
+    let scale = plot_width / axis;
+    let bottom = top + row_height * keys.len() as f64;
+
+    let label = |x: f64, y: f64, content: &str| {
+        text(x, y, content)
+            .attr("font-family", &theme.font_family)
+            .attr("font-size", 12)
+            .attr("fill", &theme.text)
+    };
+
+    let mut svg = svg().attr("height", bottom + 20.);
+    svg.push(theme.background());
+
+    let mut tick = 0.;
+    while tick <= axis {
+        let x = left + tick * scale;
+        svg.push(rect(x, top, 1., bottom - top).attr("fill", &theme.highlight));
+        svg.push(label(x, bottom + 14., &format!("{tick:.0}%")).attr("text-anchor", "middle"));
+        tick += 10.;
+    }
+
+    let mut y = top;
+    for key in keys {
+        let ideal = ideal_proportions[key];
+        let actual = share(key);
+
+        let color = match actual > ideal {
+            false => "red",
+            true => "green",
+        };
+
+        let tooltip = format!(
+            "{}<br>Actual: {:.1}% ({})<br>Ideal: {:.1}%",
+            escape(&tags.name(key)),
+            actual,
+            format_time(tree.value_at(key) as u64),
+            ideal
+        );
+
+        let range_start = ideal * (1. - TOLERANCE);
+        let range_width = ideal * 2. * TOLERANCE;
+
+        let mut g = group()
+            .attr("class", "hover-element")
+            .attr("data-tooltip", tooltip);
+        g.push(label(5., y + row_height / 2. + 4., &tags.name(key)));
+        g.push(
+            rect(
+                left + range_start * scale,
+                y + 4.,
+                range_width * scale,
+                row_height - 8.,
+            )
+            .attr("fill", &theme.body),
+        );
+        g.push(rect(left, y + 11., actual * scale, row_height - 22.).attr("fill", color));
+        g.push(
+            rect(left + ideal * scale - 1., y + 2., 3., row_height - 4.)
+                .attr("fill", tags.color(key)),
+        );
+        svg.push(g);
+
+        y += row_height;
+    }
+
+    svg.render()
+}
@@ -0,0 +1,125 @@
+use crate::idle::is_untracked;
+use crate::parse::Activity;
+use chrono::{TimeZone, Utc};
+
+fn format_date(timestamp: u64) -> String {
+    Utc.timestamp_opt(timestamp as i64, 0)
+        .unwrap()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Content lines longer than 75 octets are split onto continuation lines that
+// start with a space, without breaking a character apart (RFC 5545 3.1).
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded
+}
+
+// Joins sessions of the same tag that follow each other without a gap, which
+// happens when the same task is selected twice in a row.
+pub fn merge_adjacent(activities: &[Activity]) -> Vec<Activity> {
+    let mut merged: Vec<Activity> = Vec::new();
+    for activity in activities {
+        if let Some(last) = merged.last_mut() {
+            if last.2 == activity.2 && last.0 + last.1 as u64 == activity.0 {
+                last.1 += activity.1;
+                continue;
+            }
+        }
+        merged.push(activity.clone());
+    }
+
+    merged
+}
+
+// Only tracked time is exported, not the untracked parts of cut sessions.
+pub fn render_calendar(activities: &[Activity], merge: bool) -> String {
+    let activities: Vec<Activity> = activities
+        .iter()
+        .filter(|x| !is_untracked(&x.2))
+        .cloned()
+        .collect();
+    let activities = match merge {
+        true => merge_adjacent(&activities),
+        false => activities,
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//sankey-weighted-tree//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+
+    for (start, duration, tag) in activities {
+        let category = tag.split('.').next().unwrap();
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{start}-{}@sankey-weighted-tree",
+            escape_text(&tag)
+        ));
+        lines.push(format!("DTSTAMP:{}", format_date(now)));
+        lines.push(format!("DTSTART:{}", format_date(start)));
+        lines.push(format!("DTEND:{}", format_date(start + duration as u64)));
+        lines.push(format!("SUMMARY:{}", escape_text(&tag)));
+        lines.push(format!("CATEGORIES:{}", escape_text(category)));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    let lines: Vec<String> = lines.iter().map(|x| fold(x)).collect();
+    lines.join("\r\n") + "\r\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_lines_are_folded_at_75_octets() {
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold(&line);
+
+        for part in folded.split("\r\n") {
+            assert!(part.len() <= 75);
+        }
+        assert_eq!(folded.replace("\r\n ", ""), line);
+        assert_eq!(fold("SUMMARY:short"), "SUMMARY:short");
+    }
+
+    #[test]
+    fn untracked_time_is_not_exported() {
+        let activities = [
+            (0, 50, "work.code.rust".to_string()),
+            (50, 50, "untracked.idle.work".to_string()),
+        ];
+        let calendar = render_calendar(&activities, true);
+
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+        assert!(!calendar.contains("untracked"));
+    }
+}
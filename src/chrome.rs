@@ -0,0 +1,215 @@
+use crate::svg::{group, rect, text, Element};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::util::{format_time, local, next_midnight, start_of_day};
+
+const TITLE_HEIGHT: f64 = 24.;
+const LEGEND_ROW: f64 = 18.;
+const LEGEND_ITEM: f64 = 120.;
+const AXIS_WIDTH: f64 = 40.;
+const AXIS_HEIGHT: f64 = 18.;
+
+// Which decorations to draw around a chart.
+#[derive(Clone, Copy, Default)]
+pub struct Chrome {
+    pub title: bool,
+    pub legend: bool,
+    pub axes: bool,
+}
+
+// The part of a chart left for the data once the chrome has its room.
+pub struct Area {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Chrome {
+    // A comma separated list such as "title,legend". "all" turns on
+    // everything.
+    pub fn from_name(list: &str) -> Self {
+        let has = |name: &str| list.split(',').any(|x| x == name || x == "all");
+
+        Self {
+            title: has("title"),
+            legend: has("legend"),
+            axes: has("axes"),
+        }
+    }
+
+    fn legend_rows(&self, categories: &[String], width: f64) -> usize {
+        if !self.legend || categories.is_empty() {
+            return 0;
+        }
+
+        let per_row = ((width / LEGEND_ITEM).floor() as usize).max(1);
+        categories.len().div_ceil(per_row)
+    }
+
+    // Reserves the title above, the legend below and room for a left and a
+    // bottom axis where the chart has them.
+    pub fn area(
+        &self,
+        width: f64,
+        height: f64,
+        categories: &[String],
+        left_axis: bool,
+        bottom_axis: bool,
+    ) -> Area {
+        let top = match self.title {
+            true => TITLE_HEIGHT,
+            false => 0.,
+        };
+        let left = match self.axes && left_axis {
+            true => AXIS_WIDTH,
+            false => 0.,
+        };
+        let bottom = match self.axes && bottom_axis {
+            true => AXIS_HEIGHT,
+            false => 0.,
+        } + self.legend_rows(categories, width) as f64 * LEGEND_ROW;
+
+        Area {
+            x: left,
+            y: top,
+            width: width - left,
+            height: (height - top - bottom).max(0.),
+        }
+    }
+
+    // Everything but the axes, which depend on what the chart plots.
+    pub fn draw(
+        &self,
+        title: &str,
+        categories: &[String],
+        width: f64,
+        height: f64,
+        tags: &Registry,
+        theme: &Theme,
+    ) -> Vec<Element> {
+        let mut elements = Vec::new();
+
+        if self.title {
+            elements.push(
+                label(5., TITLE_HEIGHT - 7., title, theme)
+                    .attr("font-size", 14)
+                    .attr("font-weight", "bold"),
+            );
+        }
+
+        let rows = self.legend_rows(categories, width);
+        if rows > 0 {
+            let per_row = ((width / LEGEND_ITEM).floor() as usize).max(1);
+            let top = height - rows as f64 * LEGEND_ROW;
+
+            let mut legend = group();
+            for (i, category) in categories.iter().enumerate() {
+                let x = 5. + (i % per_row) as f64 * LEGEND_ITEM;
+                let y = top + (i / per_row) as f64 * LEGEND_ROW;
+                legend.push(rect(x, y + 4., 10., 10.).attr("fill", tags.color(category)));
+                legend.push(label(x + 14., y + 13., &tags.name(category), theme));
+            }
+            elements.push(legend);
+        }
+
+        elements
+    }
+}
+
+fn label(x: f64, y: f64, content: &str, theme: &Theme) -> Element {
+    text(x, y, content)
+        .attr("font-family", &theme.font_family)
+        .attr("font-size", 10)
+        .attr("fill", &theme.text)
+}
+
+// Tick marks and labels below the area at the given x positions.
+pub fn bottom_axis(ticks: &[(f64, String)], area: &Area, theme: &Theme) -> Element {
+    let y = area.y + area.height;
+
+    let mut axis = group();
+    axis.push(rect(area.x, y, area.width, 1.).attr("fill", &theme.text));
+    for (x, content) in ticks {
+        axis.push(rect(*x, y, 1., 4.).attr("fill", &theme.text));
+        axis.push(label(*x, y + 14., content, theme).attr("text-anchor", "middle"));
+    }
+
+    axis
+}
+
+// A bottom axis for an area that spans the given time range linearly, with
+// ticks on round local times.
+pub fn time_axis(start_timestamp: u64, end_timestamp: u64, area: &Area, theme: &Theme) -> Element {
+    let steps = [
+        60, 300, 900, 1800, 3600, 7200, 10800, 21600, 43200, 86400, 172800, 604800, 2592000,
+        7776000, 31536000,
+    ];
+    let range = end_timestamp.saturating_sub(start_timestamp).max(1);
+    let step = *steps.iter().find(|x| range / **x <= 8).unwrap_or(&31536000);
+
+    // Steps under a day are counted from local midnight so that ticks land on
+    // round hours whatever the UTC offset.
+    let mut tick = match step < 86400 {
+        true => {
+            let day = start_of_day(start_timestamp);
+            day + ((start_timestamp - day) / step + 1) * step
+        }
+        false => next_midnight(start_timestamp),
+    };
+
+    let format = match step < 86400 {
+        true => "%H:%M",
+        false => "%m/%d",
+    };
+
+    let mut ticks = Vec::new();
+    while tick < end_timestamp {
+        let x = area.x + (tick - start_timestamp) as f64 / range as f64 * area.width;
+        ticks.push((x, local(tick).format(format).to_string()));
+        tick += step;
+    }
+
+    bottom_axis(&ticks, area, theme)
+}
+
+// A left axis from 0% at the top of the area to 100% at the bottom.
+pub fn percent_axis(area: &Area, theme: &Theme) -> Element {
+    let mut axis = group();
+    axis.push(rect(area.x - 1., area.y, 1., area.height).attr("fill", &theme.text));
+    for percent in [0, 25, 50, 75, 100] {
+        let y = area.y + percent as f64 / 100. * area.height;
+        axis.push(rect(area.x - 5., y, 4., 1.).attr("fill", &theme.text));
+        axis.push(
+            label(area.x - 7., y + 3., &format!("{percent}%"), theme).attr("text-anchor", "end"),
+        );
+    }
+
+    axis
+}
+
+// A bar showing the height of a round duration, for charts where height is
+// time but there is no linear axis to read it from. It sits at the right end
+// of the bottom axis row, which the area must have reserved.
+pub fn scale_bar(pixels_per_second: f64, area: &Area, theme: &Theme) -> Element {
+    let steps = [
+        60, 300, 900, 1800, 3600, 7200, 14400, 28800, 86400, 604800, 2592000,
+    ];
+    let fits = |x: &&u64| **x as f64 * pixels_per_second <= AXIS_HEIGHT - 4.;
+    let step = *steps.iter().rev().find(fits).unwrap_or(&60);
+    let bar = step as f64 * pixels_per_second;
+
+    let x = area.x + area.width - 80.;
+    let bottom = area.y + area.height + AXIS_HEIGHT - 2.;
+
+    let mut scale = group();
+    scale.push(rect(x, bottom - bar, 6., bar).attr("fill", &theme.text));
+    scale.push(label(
+        x + 10.,
+        bottom - 2.,
+        &format!("= {}", format_time(step)),
+        theme,
+    ));
+
+    scale
+}
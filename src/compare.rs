@@ -0,0 +1,213 @@
+use crate::sources::load_activities;
+use crate::svg::{rect, svg, text, Element};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::util::{escape, format_time};
+
+struct Change {
+    label: String,
+    depth: usize,
+    before: f64,
+    after: f64,
+}
+
+impl Change {
+    fn delta(&self) -> f64 {
+        self.after - self.before
+    }
+
+    // None when there was nothing to compare against.
+    fn percent(&self) -> Option<f64> {
+        match self.before > 0. {
+            true => Some(100. * self.delta() / self.before),
+            false => None,
+        }
+    }
+}
+
+fn format_delta(delta: f64) -> String {
+    match delta < 0. {
+        true => format!("-{}", format_time(-delta as u64)),
+        false => format!("+{}", format_time(delta as u64)),
+    }
+}
+
+fn format_percent(change: &Change) -> String {
+    match change.percent() {
+        Some(x) => format!("{:+.1}%", x),
+        None => "new".to_string(),
+    }
+}
+
+// Categories and their subcategories, biggest changes first within each
+// parent so the rows read like the table of a Sankey.
+fn load_changes(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    previous_start: u64,
+    previous_end: u64,
+) -> Vec<Change> {
+    let (current, _, _) = load_activities(start_timestamp, end_timestamp);
+    let (previous, _, _) = load_activities(previous_start, previous_end);
+    let difference = current.subtract(&previous);
+
+    // Subcategories sort under their category by the category's change, then
+    // by their own.
+    let mut nodes: Vec<(String, usize, f64)> = difference
+        .walk()
+        .into_iter()
+        .map(|(label, x)| {
+            let depth = label.split('.').count();
+            (label, depth, x.value)
+        })
+        .filter(|x| x.1 <= 2)
+        .collect();
+    let category = |label: &str| label.split('.').next().unwrap().to_string();
+    nodes.sort_by(|a, b| {
+        let (x, y) = (category(&a.0), category(&b.0));
+        difference
+            .value_at(&y)
+            .abs()
+            .partial_cmp(&difference.value_at(&x).abs())
+            .unwrap()
+            .then(x.cmp(&y))
+            .then(a.1.cmp(&b.1))
+            .then(b.2.abs().partial_cmp(&a.2.abs()).unwrap())
+            .then(a.0.cmp(&b.0))
+    });
+
+    let changes: Vec<Change> = nodes
+        .into_iter()
+        .map(|(label, depth, _)| Change {
+            before: previous.value_at(&label),
+            after: current.value_at(&label),
+            label,
+            depth,
+        })
+        .collect();
+
+    changes
+}
+
+// Bars grow right for time gained and left for time lost from a shared
+// center line.
+fn draw_chart(changes: &[Change], width: f64, tags: &Registry, theme: &Theme) -> Element {
+    let row_height = 20.;
+    let label_width = 0.2 * width;
+    let center = label_width + (width - label_width) / 2.;
+    let reach = (width - label_width) / 2. - 10.;
+    let height = row_height * changes.len() as f64 + 10.;
+
+    let largest = changes
+        .iter()
+        .fold(0., |acc: f64, x| acc.max(x.delta().abs()));
+    let scale = match largest > 0. {
+        true => reach / largest,
+        false => 0.,
+    };
+
+    let mut svg = svg().attr("height", height);
+    svg.push(theme.background());
+    svg.push(rect(center, 0., 1., height).attr("fill", &theme.body));
+
+    let mut y = 5.;
+    for change in changes {
+        let delta = change.delta();
+        let length = delta.abs() * scale;
+        let x = match delta < 0. {
+            true => center - length,
+            false => center,
+        };
+
+        let tooltip = format!(
+            "{}: {} &rarr; {}<br>{} ({})",
+            escape(&tags.name(&change.label)),
+            format_time(change.before as u64),
+            format_time(change.after as u64),
+            format_delta(delta),
+            format_percent(change)
+        );
+
+        let indent = 5. + 15. * (change.depth - 1) as f64;
+        svg.push(
+            text(indent, y + row_height / 2. + 4., &tags.name(&change.label))
+                .attr("font-family", &theme.font_family)
+                .attr("font-size", 12)
+                .attr("fill", &theme.text),
+        );
+        svg.push(
+            rect(x, y + 2., length, row_height - 4.)
+                .attr("class", "hover-element")
+                .attr("data-tooltip", tooltip)
+                .attr("fill", tags.color(&change.label)),
+        );
+
+        y += row_height;
+    }
+
+    svg
+}
+
+fn render_table(changes: &[Change], tags: &Registry) -> String {
+    let mut out = String::from("<span class='compare-container'>");
+
+    out += "<span>Category</span>";
+    out += "<span>Before</span>";
+    out += "<span>After</span>";
+    out += "<span>Change</span>";
+    out += "<span>Change %</span>";
+
+    for change in changes {
+        let color = match change.delta() < 0. {
+            false => "green",
+            true => "red",
+        };
+        let weight = match change.depth {
+            1 => "bold",
+            _ => "normal",
+        };
+
+        out += format!(
+            "<span style='font-weight: {weight}; padding-left: {}rem'>{}</span>",
+            change.depth - 1,
+            escape(&tags.name(&change.label))
+        )
+        .as_str();
+        out += format!("<span>{}</span>", format_time(change.before as u64)).as_str();
+        out += format!("<span>{}</span>", format_time(change.after as u64)).as_str();
+        out += format!(
+            "<span style='color: {color}'>{}</span>",
+            format_delta(change.delta())
+        )
+        .as_str();
+        out += format!(
+            "<span style='color: {color}'>{}</span>",
+            format_percent(change)
+        )
+        .as_str();
+    }
+
+    out + "</span>"
+}
+
+pub fn render_compare(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    previous_start: u64,
+    previous_end: u64,
+    width: f64,
+    tags: &Registry,
+    theme: &Theme,
+) -> String {
+    let changes = load_changes(start_timestamp, end_timestamp, previous_start, previous_end);
+
+    let mut out = format!(
+        "<div style='color: {}; font-family: {}'>",
+        escape(&theme.text),
+        escape(&theme.font_family)
+    );
+    out += draw_chart(&changes, width, tags, theme).render().as_str();
+    out += render_table(&changes, tags).as_str();
+
+    out + "</div>"
+}
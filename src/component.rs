@@ -1,4 +1,44 @@
 use crate::point::Point;
+use crate::svg::{path, rect, text, Element, PathData};
+
+// What to do with a label that does not fit inside its node.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LabelMode {
+    Hide,
+    Truncate,
+    Outside,
+}
+
+impl LabelMode {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "hide" => LabelMode::Hide,
+            "outside" => LabelMode::Outside,
+            _ => LabelMode::Truncate,
+        }
+    }
+}
+
+// Average advance of a character relative to the font size, close enough for
+// Verdana and most sans-serif fonts to decide whether a label fits.
+pub const CHAR_WIDTH: f64 = 0.6;
+
+// Shortens text to the given width, ending it with an ellipsis. Returns None
+// when not even one character and the ellipsis fit.
+fn fit_text(text: &str, width: f64, font_size: f64) -> Option<String> {
+    let capacity = (width / (CHAR_WIDTH * font_size)).floor() as usize;
+    let length = text.chars().count();
+
+    if length <= capacity {
+        return Some(text.to_string());
+    }
+
+    if capacity < 2 {
+        return None;
+    }
+
+    Some(text.chars().take(capacity - 1).collect::<String>() + "\u{2026}")
+}
 
 pub struct Component {
     color: String,
@@ -10,6 +50,10 @@ pub struct Component {
     text2: String,
     data: String,
     font_size: f64,
+    text_color: String,
+    font_family: String,
+    label_mode: LabelMode,
+    outside_width: f64,
 }
 
 impl Component {
@@ -24,6 +68,10 @@ impl Component {
             text2: "".to_string(),
             data: "".to_string(),
             font_size: 12.,
+            text_color: "#eee".to_string(),
+            font_family: "Verdana".to_string(),
+            label_mode: LabelMode::Truncate,
+            outside_width: 0.,
         }
     }
 
@@ -55,13 +103,26 @@ impl Component {
         self.font_size = size;
     }
 
-    pub fn draw(&self) -> String {
-        let color = &self.color;
+    pub fn set_text_color(&mut self, color: String) {
+        self.text_color = color;
+    }
+
+    pub fn set_font_family(&mut self, family: String) {
+        self.font_family = family;
+    }
+
+    pub fn set_label_mode(&mut self, mode: LabelMode) {
+        self.label_mode = mode;
+    }
+
+    pub fn set_outside_width(&mut self, width: f64) {
+        self.outside_width = width;
+    }
+
+    pub fn draw(&self) -> Vec<Element> {
         let a = &self.a;
         let b = &self.b;
-        let bar_height = &self.bar_height;
-        let text1 = &self.text1;
-        let text2 = &self.text2;
+        let bar_height = self.bar_height;
         let text_padding = 5.;
 
         let mx = (a.x + b.x) / 2.;
@@ -75,47 +136,68 @@ impl Component {
         let bottom_right = Point::new(b.x, b.y + bar_height);
         let bottom_left = Point::new(a.x, a.y + bar_height);
 
-        let mut s = String::new();
-        s += format!(
-            "<path class='hover-element' data-tooltip='{}' d='",
-            &self.data
-        )
-        .as_str();
-
-        s += format!("M {} {}", top_left.x, top_left.y).as_str();
-
-        // Beziér curve across the top
-        s += format!(
-            "C {} {}, {} {}, {} {}",
-            mx, top_left.y, mx, top_right.y, top_right.x, top_right.y
-        )
-        .as_str();
-
-        s += format!("L {} {}", bottom_right.x, bottom_right.y).as_str();
-
-        // Beziér curve across the bottom
-        s += format!(
-            "C {} {}, {} {}, {} {}",
-            mx, bottom_right.y, mx, bottom_left.y, bottom_left.x, bottom_left.y
-        )
-        .as_str();
-
-        s += format!("' stroke='none' fill='{}' />\n", self.body_color).as_str();
-
-        s += format!(
-            "<rect x='{}' y='{}' width='{bar_width}' height='{bar_height}' fill='{color}' />\n",
-            b.x, b.y
-        )
-        .as_str();
+        let data = PathData::new()
+            .move_to(top_left.x, top_left.y)
+            // Beziér curve across the top
+            .curve_to(
+                (mx, top_left.y),
+                (mx, top_right.y),
+                top_right.x,
+                top_right.y,
+            )
+            .line_to(bottom_right.x, bottom_right.y)
+            // Beziér curve across the bottom
+            .curve_to(
+                (mx, bottom_right.y),
+                (mx, bottom_left.y),
+                bottom_left.x,
+                bottom_left.y,
+            );
 
         let font_size = self.font_size;
-        s += format!(
-            "<text x='{}' y='{}' font-family='Verdana' font-size='{font_size}' fill='#eee'>{text1}</text>\n",
-            left_text.x, left_text.y+font_size/4.
-        )
-        .as_str();
-        s += format!("<text x='{}' y='{}' font-family='Verdana' font-size='{font_size}' fill='#eee' text-anchor='end'>{text2}</text>\n", right_text.x, right_text.y + font_size/4.).as_str();
-
-        s
+        let inner_width = b.x - a.x - 2. * text_padding;
+        let fits = bar_height >= font_size;
+
+        let mut elements = vec![
+            path(data)
+                .attr("class", "hover-element")
+                .attr("data-tooltip", &self.data)
+                .attr("stroke", "none")
+                .attr("fill", &self.body_color),
+            rect(b.x, b.y, bar_width, bar_height).attr("fill", &self.color),
+        ];
+
+        let label = |x: f64, y: f64, content: &str| {
+            text(x, y + font_size / 4., content)
+                .attr("font-family", &self.font_family)
+                .attr("font-size", font_size)
+                .attr("fill", &self.text_color)
+        };
+
+        // Only whole labels are hidden or truncated here; the full text is
+        // always available in the tooltip.
+        let fitted = |content: &str, width: f64| match self.label_mode {
+            LabelMode::Hide if content.chars().count() as f64 * CHAR_WIDTH * font_size > width => {
+                None
+            }
+            _ => fit_text(content, width, font_size),
+        };
+
+        if fits {
+            if let Some(content) = fitted(&self.text1, inner_width) {
+                elements.push(label(left_text.x, left_text.y, &content));
+            }
+            if let Some(content) = fitted(&self.text2, inner_width) {
+                elements
+                    .push(label(right_text.x, right_text.y, &content).attr("text-anchor", "end"));
+            }
+        } else if self.label_mode == LabelMode::Outside {
+            let x = b.x + bar_width + text_padding;
+            if let Some(content) = fit_text(&self.text2, self.outside_width, font_size) {
+                elements.push(label(x, right_text.y, &content));
+            }
+        }
+
+        elements
     }
 }
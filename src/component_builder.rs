@@ -1,5 +1,6 @@
-use crate::component::Component;
+use crate::component::{Component, LabelMode};
 use crate::point::Point;
+use crate::theme::Theme;
 
 pub struct ComponentBuilder {
     component: Component,
@@ -47,6 +48,33 @@ impl ComponentBuilder {
         self
     }
 
+    pub fn text_color(mut self, color: &str) -> ComponentBuilder {
+        self.component.set_text_color(color.to_string());
+        self
+    }
+
+    pub fn font_family(mut self, family: &str) -> ComponentBuilder {
+        self.component.set_font_family(family.to_string());
+        self
+    }
+
+    pub fn theme(mut self, theme: &Theme) -> ComponentBuilder {
+        self.component.set_body_color(theme.body.clone());
+        self.component.set_text_color(theme.text.clone());
+        self.component.set_font_family(theme.font_family.clone());
+        self
+    }
+
+    pub fn label_mode(mut self, mode: LabelMode) -> ComponentBuilder {
+        self.component.set_label_mode(mode);
+        self
+    }
+
+    pub fn outside_width(mut self, width: f64) -> ComponentBuilder {
+        self.component.set_outside_width(width);
+        self
+    }
+
     pub fn build(self) -> Component {
         self.component
     }
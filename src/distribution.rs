@@ -0,0 +1,132 @@
+use crate::sources::load_activities;
+use crate::svg::{group, rect, svg, text};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::util::{escape, format_time, local, next_hour, split_sessions};
+use chrono::{Datelike, Timelike};
+use std::collections::BTreeMap;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+// Seconds spent in each weekday and hour of the day, Monday first.
+type Grid = [[f64; 24]; 7];
+
+// Groups tracked time by the tag one level below the prefix, or by top-level
+// category without one. Sessions are cut at every hour so each piece falls in
+// a single cell.
+fn distribution(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    prefix: Option<&str>,
+) -> BTreeMap<String, Grid> {
+    let (_, _, activities) = load_activities(start_timestamp, end_timestamp);
+
+    let depth = match prefix {
+        Some(x) => x.split('.').count() + 1,
+        None => 1,
+    };
+
+    let mut grids: BTreeMap<String, Grid> = BTreeMap::new();
+    for (start, duration, name) in split_sessions(&activities, next_hour) {
+        if let Some(prefix) = prefix {
+            if !name.starts_with(&format!("{prefix}.")) {
+                continue;
+            }
+        }
+
+        let key = name.split('.').take(depth).collect::<Vec<&str>>().join(".");
+        let time = local(start);
+        let weekday = time.weekday().num_days_from_monday() as usize;
+        let hour = time.hour() as usize;
+
+        grids.entry(key).or_insert([[0.; 24]; 7])[weekday][hour] += duration as f64;
+    }
+
+    grids
+}
+
+// One 24 by 7 grid per tag, each shaded against its own busiest hour so the
+// pattern of a small category is as visible as that of a large one.
+pub fn render_distribution(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    prefix: Option<&str>,
+    width: f64,
+    tags: &Registry,
+    theme: &Theme,
+) -> String {
+    let grids = distribution(start_timestamp, end_timestamp, prefix);
+
+    let cell = 10.;
+    let gap = 1.;
+    let left = 30.;
+    let top = 30.;
+    let grid_width = left + 24. * (cell + gap) + 20.;
+    let grid_height = top + 7. * (cell + gap) + 10.;
+    let per_row = ((width / grid_width).floor() as usize).max(1);
+    let rows = grids.len().div_ceil(per_row);
+
+    let label = |x: f64, y: f64, content: &str| {
+        text(x, y, content)
+            .attr("font-family", &theme.font_family)
+            .attr("font-size", 10)
+            .attr("fill", &theme.text)
+    };
+
+    let mut svg = svg().attr("height", rows as f64 * grid_height);
+    svg.push(theme.background());
+
+    for (index, (key, grid)) in grids.iter().enumerate() {
+        let x0 = (index % per_row) as f64 * grid_width;
+        let y0 = (index / per_row) as f64 * grid_height;
+
+        let largest = grid.iter().flatten().fold(0., |acc: f64, x| acc.max(*x));
+        let total: f64 = grid.iter().flatten().sum();
+        let color = tags.color(key);
+
+        let mut g = group();
+        g.push(label(
+            x0 + left,
+            y0 + 12.,
+            &format!("{} ({})", tags.name(key), format_time(total as u64)),
+        ));
+        for hour in (0..24).step_by(6) {
+            g.push(label(
+                x0 + left + hour as f64 * (cell + gap),
+                y0 + top - 4.,
+                &format!("{hour:02}"),
+            ));
+        }
+
+        for (weekday, hours) in grid.iter().enumerate() {
+            let y = y0 + top + weekday as f64 * (cell + gap);
+            g.push(label(x0, y + cell - 1., WEEKDAYS[weekday]));
+
+            for (hour, value) in hours.iter().enumerate() {
+                let x = x0 + left + hour as f64 * (cell + gap);
+                let tooltip = format!(
+                    "{}<br>{} {hour:02}:00<br>{}",
+                    escape(&tags.name(key)),
+                    WEEKDAYS[weekday],
+                    format_time(*value as u64)
+                );
+
+                let (fill, opacity) = match *value > 0. {
+                    true => (color.as_str(), 0.15 + 0.85 * value / largest),
+                    false => (theme.body.as_str(), 1.),
+                };
+                g.push(
+                    rect(x, y, cell, cell)
+                        .attr("class", "hover-element")
+                        .attr("data-tooltip", tooltip)
+                        .attr("fill", fill)
+                        .attr("fill-opacity", opacity),
+                );
+            }
+        }
+
+        svg.push(g);
+    }
+
+    svg.render()
+}
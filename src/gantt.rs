@@ -0,0 +1,162 @@
+use crate::chrome::{time_axis, Area, Chrome};
+use crate::parse::Activity;
+use crate::sources::load_activities;
+use crate::svg::{rect, svg, text};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::util::{escape, format_time, local, next_midnight, split_sessions, start_of_day};
+use chrono::{NaiveDate, Timelike};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rows {
+    Day,
+    Category,
+}
+
+impl Rows {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "day" => Some(Rows::Day),
+            "category" => Some(Rows::Category),
+            _ => None,
+        }
+    }
+}
+
+pub struct GanttOptions {
+    pub width: f64,
+    pub height: f64,
+    pub rows: Rows,
+}
+
+const DAY: f64 = 60. * 60. * 24.;
+
+// Splits sessions at local midnight so every piece lies within one day.
+fn split_days(activities: &[Activity]) -> Vec<(NaiveDate, Activity)> {
+    split_sessions(activities, next_midnight)
+        .into_iter()
+        .map(|x| (local(x.0).date_naive(), x))
+        .collect()
+}
+
+// Lays sessions out on a time-of-day axis with one row per day or per
+// top-level category, so idle gaps show up as empty space.
+pub fn render_gantt(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    tags: &Registry,
+    theme: &Theme,
+    chrome: &Chrome,
+    options: &GanttOptions,
+) -> String {
+    let (width, height, rows) = (options.width, options.height, options.rows);
+
+    let (_, _, activities) = load_activities(start_timestamp, end_timestamp);
+
+    let mut categories: Vec<String> = activities
+        .iter()
+        .map(|x| x.2.split('.').next().unwrap().to_string())
+        .collect();
+    categories.sort();
+    categories.dedup();
+
+    let mut grouped: BTreeMap<String, (String, Vec<Activity>)> = BTreeMap::new();
+    for (date, activity) in split_days(&activities) {
+        let (key, label) = match rows {
+            Rows::Day => (date.to_string(), date.format("%a %m/%d").to_string()),
+            Rows::Category => {
+                let category = activity.2.split('.').next().unwrap();
+                (category.to_string(), tags.name(category))
+            }
+        };
+
+        grouped
+            .entry(key)
+            .or_insert((label, Vec::new()))
+            .1
+            .push(activity);
+    }
+
+    // With axes on, the hour labels above the rows give way to a time axis
+    // below them.
+    let area = chrome.area(width, height, &categories, false, true);
+    let left = area.x + 90.;
+    let top = match chrome.axes {
+        true => area.y,
+        false => area.y + 20.,
+    };
+    let plot_width = area.width - 90. - 10.;
+    let row_height = match grouped.is_empty() {
+        true => 0.,
+        false => ((area.y + area.height - top) / grouped.len() as f64).min(40.),
+    };
+    let bottom = top + row_height * grouped.len() as f64;
+
+    let label = |x: f64, y: f64, content: &str| {
+        text(x, y, content)
+            .attr("font-family", &theme.font_family)
+            .attr("font-size", 12)
+            .attr("fill", &theme.text)
+    };
+
+    let mut svg = svg();
+    svg.push(theme.background());
+
+    // Label every hour when there is room and every third hour otherwise.
+    let step = match plot_width / 24. > 40. {
+        true => 1,
+        false => 3,
+    };
+    for hour in 0..=24 {
+        let x = left + hour as f64 / 24. * plot_width;
+        svg.push(rect(x, top, 1., bottom - top).attr("fill", &theme.body));
+        if hour % step == 0 && !chrome.axes {
+            svg.push(label(x, top - 6., &format!("{hour:02}:00")).attr("text-anchor", "middle"));
+        }
+    }
+
+    let mut y = top;
+    for (label_text, sessions) in grouped.values() {
+        svg.push(label(5., y + row_height / 2. + 4., label_text));
+
+        for (start, duration, name) in sessions {
+            let time = local(*start);
+            let x = left + time.num_seconds_from_midnight() as f64 / DAY * plot_width;
+            let length = *duration as f64 / DAY * plot_width;
+
+            let tooltip = format!(
+                "{}<br>{}<br>{}",
+                escape(&tags.name(name)),
+                time,
+                format_time(*duration as u64)
+            );
+            svg.push(
+                rect(x, y + 2., length, row_height - 4.)
+                    .attr("class", "hover-element")
+                    .attr("data-tooltip", tooltip)
+                    .attr("fill", tags.color(name)),
+            );
+        }
+
+        y += row_height;
+    }
+
+    if chrome.axes {
+        let plot = Area {
+            x: left,
+            y: top,
+            width: plot_width,
+            height: bottom - top,
+        };
+        let day = start_of_day(start_timestamp);
+        svg.push(time_axis(day, day + DAY as u64, &plot, theme));
+    }
+    let title = match rows {
+        Rows::Day => "Sessions by day",
+        Rows::Category => "Sessions by category",
+    };
+    svg.extend(chrome.draw(title, &categories, width, height, tags, theme));
+
+    svg.render()
+}
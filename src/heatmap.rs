@@ -0,0 +1,111 @@
+use crate::render::get_points;
+use crate::svg::{rect, svg, text};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::timeline::{aggregate, Granularity};
+use crate::util::{escape, format_time, local, midnight};
+use chrono::{Datelike, Days, NaiveDate};
+use std::collections::HashMap;
+
+// One square per day in columns of weeks, shaded by the day's points or by the
+// hours spent in one category.
+pub fn render_heatmap(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    ideal_proportions: &HashMap<String, f64>,
+    category: Option<&str>,
+    tags: &Registry,
+    theme: &Theme,
+) -> String {
+    // Days are counted from local midnight so each square is a calendar day.
+    let first = local(start_timestamp).date_naive();
+    let values: HashMap<NaiveDate, f64> =
+        aggregate(midnight(first), end_timestamp, Granularity::Day)
+            .into_iter()
+            .filter(|x| !x.2.children.is_empty())
+            .map(|(day, _, tree)| {
+                let value = match category {
+                    Some(x) => tree.value_at(x),
+                    None => get_points(&tree, ideal_proportions),
+                };
+                (local(day).date_naive(), value)
+            })
+            .collect();
+
+    let largest = values.values().fold(0., |acc: f64, x| acc.max(*x));
+    let color = match category {
+        Some(x) => tags.color(x),
+        None => "#59a14f".to_string(),
+    };
+
+    let cell = 12.;
+    let gap = 2.;
+    let left = 30.;
+    let top = 20.;
+
+    let last = local(end_timestamp).date_naive();
+    let first_week = first - Days::new(first.weekday().num_days_from_sunday() as u64);
+    let weeks = (last - first_week).num_days() / 7 + 1;
+
+    let label = |x: f64, y: f64, content: &str| {
+        text(x, y, content)
+            .attr("font-family", &theme.font_family)
+            .attr("font-size", 10)
+            .attr("fill", &theme.text)
+    };
+
+    let mut svg = svg()
+        .attr("width", left + weeks as f64 * (cell + gap))
+        .attr("height", top + 7. * (cell + gap));
+    svg.push(theme.background());
+
+    for (row, name) in [(1, "Mon"), (3, "Wed"), (5, "Fri")] {
+        svg.push(label(0., top + row as f64 * (cell + gap) + cell - 2., name));
+    }
+
+    let mut day = first;
+    while day <= last {
+        let column = (day - first_week).num_days() / 7;
+        let row = day.weekday().num_days_from_sunday();
+        let x = left + column as f64 * (cell + gap);
+        let y = top + row as f64 * (cell + gap);
+
+        if day.day() == 1 || (day == first && first.day() < 22) {
+            svg.push(label(x, top - 6., &day.format("%b").to_string()));
+        }
+
+        let value = values.get(&day).copied();
+        let summary = match (value, category) {
+            (None, _) => "no data".to_string(),
+            (Some(x), Some(category)) => {
+                format!(
+                    "{}: {}",
+                    escape(&tags.name(category)),
+                    format_time(x as u64)
+                )
+            }
+            (Some(x), None) => format!("{:.3} points", x),
+        };
+
+        let opacity = match value {
+            Some(x) if largest > 0. => 0.15 + 0.85 * x / largest,
+            _ => 1.,
+        };
+        let fill = match value {
+            Some(_) => color.as_str(),
+            None => theme.body.as_str(),
+        };
+
+        svg.push(
+            rect(x, y, cell, cell)
+                .attr("class", "hover-element")
+                .attr("data-tooltip", format!("{day}<br>{summary}"))
+                .attr("fill", fill)
+                .attr("fill-opacity", opacity),
+        );
+
+        day = day + Days::new(1);
+    }
+
+    svg.render()
+}
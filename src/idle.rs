@@ -0,0 +1,135 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+pub const UNTRACKED: &str = "untracked";
+
+// Limits on how long a session can plausibly last before the tag was most
+// likely left running by mistake.
+#[derive(Default)]
+pub struct IdleRules {
+    // Sessions of a tag without a limit of its own are cut after this.
+    idle: Option<i64>,
+    // Sessions of a tag are cut after the limit of the longest matching prefix,
+    // with "*" matching every tag.
+    limits: Vec<(String, i64)>,
+}
+
+impl IdleRules {
+    // The limit of the longest matching prefix, or the idle time when no
+    // prefix matches.
+    pub fn limit(&self, tag: &str) -> Option<i64> {
+        self.limits
+            .iter()
+            .filter(|(prefix, _)| {
+                prefix == "*"
+                    || tag == prefix
+                    || (tag.starts_with(prefix.as_str()) && tag[prefix.len()..].starts_with('.'))
+            })
+            .max_by_key(|(prefix, _)| match prefix.as_str() {
+                "*" => 0,
+                x => x.len() + 1,
+            })
+            .map(|x| x.1)
+            .or(self.idle)
+    }
+
+    pub fn is_suspicious(&self, tag: &str, duration: i64) -> bool {
+        self.limit(tag).is_some_and(|x| duration > x)
+    }
+
+    // The parts of the session from start to end that keep the tag and that
+    // go to the untracked bucket.
+    pub fn split(&self, start: u64, end: u64, tag: &str) -> Vec<(u64, u64, String)> {
+        match self.limit(tag) {
+            Some(limit) if end > start + limit as u64 => vec![
+                (start, start + limit as u64, tag.to_string()),
+                (start + limit as u64, end, untracked(tag)),
+            ],
+            _ => vec![(start, end, tag.to_string())],
+        }
+    }
+}
+
+// Untracked time is kept under the category it was taken from.
+pub fn untracked(tag: &str) -> String {
+    format!("{UNTRACKED}.idle.{}", tag.split('.').next().unwrap())
+}
+
+pub fn is_untracked(tag: &str) -> bool {
+    tag.split('.').next() == Some(UNTRACKED)
+}
+
+// The idle file holds an "idle <seconds>" line and any number of
+// "max <prefix> <seconds>" lines, e.g. "max work 14400" or "max * 28800".
+// The idle time applies to tags that no max line matches. Without the file
+// sessions are never cut.
+pub fn parse_idle_rules(filename: &str) -> IdleRules {
+    let mut rules = IdleRules::default();
+    if !Path::new(filename).exists() {
+        return rules;
+    }
+
+    let mut file = File::open(filename).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split(' ').collect();
+        match words[..] {
+            ["idle", seconds] => rules.idle = Some(seconds.parse().unwrap()),
+            ["max", prefix, seconds] => rules
+                .limits
+                .push((prefix.to_string(), seconds.parse().unwrap())),
+            _ => panic!("Invalid idle file"),
+        }
+    }
+
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> IdleRules {
+        IdleRules {
+            idle: Some(100),
+            limits: vec![("work".to_string(), 50), ("work.code".to_string(), 200)],
+        }
+    }
+
+    #[test]
+    fn the_longest_prefix_sets_the_limit() {
+        let rules = rules();
+        assert_eq!(rules.limit("work.meet.standup"), Some(50));
+        assert_eq!(rules.limit("work.code.rust"), Some(200));
+        assert_eq!(rules.limit("workout.gym.lift"), Some(100));
+    }
+
+    #[test]
+    fn tags_without_a_limit_are_cut_after_the_idle_time() {
+        let rules = rules();
+        assert_eq!(
+            rules.split(0, 150, "fun.games.chess"),
+            vec![
+                (0, 100, "fun.games.chess".to_string()),
+                (100, 150, "untracked.idle.fun".to_string()),
+            ]
+        );
+        assert_eq!(
+            rules.split(0, 150, "work.code.rust"),
+            vec![(0, 150, "work.code.rust".to_string())]
+        );
+        assert!(rules.is_suspicious("fun.games.chess", 150));
+        assert!(!rules.is_suspicious("work.code.rust", 150));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::chrome::Chrome;
+use crate::component::LabelMode;
+use crate::tree_node::{Threshold, TreeNode};
+
+#[derive(Clone, Copy)]
+pub enum Order {
+    Value,
+    Name,
+}
+
+impl Order {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "name" => Order::Name,
+            _ => Order::Value,
+        }
+    }
+}
+
+// The hierarchy chart drawn from the activity tree.
+#[derive(Clone, Copy)]
+pub enum Chart {
+    Sankey,
+    Sunburst,
+    Treemap,
+}
+
+impl Chart {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "sunburst" => Chart::Sunburst,
+            "treemap" => Chart::Treemap,
+            _ => Chart::Sankey,
+        }
+    }
+}
+
+pub struct SankeyOptions {
+    pub width: f64,
+    pub height: f64,
+    pub order: Order,
+    pub labels: LabelMode,
+    pub threshold: Threshold,
+    pub chart: Chart,
+    pub chrome: Chrome,
+}
+
+pub struct LayoutNode {
+    pub label: String,
+    pub depth: usize,
+    pub value: f64,
+    // Top of the node in its own column.
+    pub top: f64,
+    // Top of the slot the node's ribbon leaves from in its parent.
+    pub source: f64,
+    pub height: f64,
+}
+
+pub fn sorted_children(tree: &TreeNode, order: Order) -> Vec<(&String, &TreeNode)> {
+    let mut children: Vec<(&String, &TreeNode)> = tree.children.iter().collect();
+    match order {
+        Order::Value => children.sort_by(|a, b| {
+            b.1.value
+                .partial_cmp(&a.1.value)
+                .unwrap()
+                .then_with(|| a.0.cmp(b.0))
+        }),
+        Order::Name => children.sort_by(|a, b| a.0.cmp(b.0)),
+    }
+
+    children
+}
+
+// Each entry holds the node's dotted label, the node and the index of its
+// parent in the previous column.
+type Column<'a> = Vec<(String, &'a TreeNode, Option<usize>)>;
+
+// Lists the nodes of every column in parent order. Keeping children grouped
+// under their parent, in the same order as the parents themselves, is what
+// keeps ribbons from crossing.
+fn columns(tree: &TreeNode, order: Order) -> Vec<Column<'_>> {
+    let mut columns: Vec<Column> = vec![vec![(String::new(), tree, None)]];
+
+    loop {
+        let mut next = Vec::new();
+        for (index, (label, node, _)) in columns.last().unwrap().iter().enumerate() {
+            for (key, child) in sorted_children(node, order) {
+                let label = match label.is_empty() {
+                    true => key.clone(),
+                    false => format!("{label}.{key}"),
+                };
+                next.push((label, child, Some(index)));
+            }
+        }
+
+        if next.is_empty() {
+            break;
+        }
+        columns.push(next);
+    }
+
+    columns
+}
+
+// Positions every node of the tree between `margin` and `height - margin`.
+// All columns share one value scale. The gap between nodes is a fixed share of
+// the available height divided among the gaps of the busiest column, and each
+// column is centered vertically.
+pub fn layout(tree: &TreeNode, height: f64, margin: f64, order: Order) -> Vec<LayoutNode> {
+    let available = height - 2. * margin;
+    let columns = columns(tree, order);
+
+    let busiest = columns.iter().map(|x| x.len()).max().unwrap();
+    let padding = match busiest > 1 {
+        true => (0.2 * available / (busiest - 1) as f64).min(0.05 * available),
+        false => 0.,
+    };
+    let scale = match tree.value > 0. {
+        true => (available - padding * (busiest as f64 - 1.)) / tree.value,
+        false => 0.,
+    };
+
+    let mut nodes = Vec::new();
+    let mut slots = vec![margin + (available - tree.value * scale) / 2.];
+    for (depth, column) in columns.iter().enumerate().skip(1) {
+        let used = tree.value * scale + padding * (column.len() as f64 - 1.);
+        let mut top = margin + (available - used) / 2.;
+
+        let mut column_slots = Vec::new();
+        for (label, node, parent) in column {
+            let parent = parent.unwrap();
+            let height = node.value * scale;
+
+            nodes.push(LayoutNode {
+                label: label.clone(),
+                depth,
+                value: node.value,
+                top,
+                source: slots[parent],
+                height,
+            });
+
+            slots[parent] += height;
+            column_slots.push(top);
+            top += height + padding;
+        }
+
+        slots = column_slots;
+    }
+
+    nodes
+}
@@ -1,22 +1,60 @@
+pub mod activitywatch;
+pub mod aliases;
+pub mod bullet;
+pub mod calendar;
+pub mod chrome;
+pub mod compare;
 pub mod component;
 pub mod component_builder;
+pub mod distribution;
+pub mod gantt;
+pub mod heatmap;
+pub mod idle;
+pub mod layout;
+pub mod palette;
 pub mod parse;
+pub mod plan;
 pub mod point;
 pub mod render;
+pub mod sources;
+pub mod streamgraph;
+pub mod sunburst;
+pub mod svg;
+pub mod tags;
+pub mod theme;
 pub mod timeline;
 pub mod tree;
 pub mod tree_node;
+pub mod treemap;
 pub mod util;
 
-use parse::parse_file;
+use bullet::render_bullet;
+use calendar::render_calendar;
+use chrome::Chrome;
+use compare::render_compare;
+use component::LabelMode;
+use distribution::render_distribution;
+use gantt::{render_gantt, GanttOptions, Rows};
+use heatmap::render_heatmap;
+use layout::{Chart, Order, SankeyOptions};
+use palette::Palette;
+use render::render_aliases;
 use render::render_band;
+use render::render_idle;
+use render::render_overlaps;
+use render::render_plan;
 use render::render_sankey;
 use render::render_table;
+use sources::load_activities;
 use std::collections::HashMap;
 use std::io::Read;
+use streamgraph::{render_streamgraph, Step, StreamOptions};
+use tags::Registry;
+use theme::Theme;
 use tide::Request;
 use tide::Response;
-use timeline::draw_timeline;
+use timeline::{draw_timeline, Granularity, TimelineOptions};
+use tree_node::Threshold;
 use tree_node::TreeNode;
 
 fn get_ideal_proportions(timestamp: u64) -> HashMap<String, f64> {
@@ -25,7 +63,7 @@ fn get_ideal_proportions(timestamp: u64) -> HashMap<String, f64> {
     file.read_to_string(&mut contents).unwrap();
     let mut ideal_proportions: HashMap<String, f64> = HashMap::new();
     for line in contents.lines() {
-        if line.len() == 0 {
+        if line.is_empty() {
             continue;
         }
 
@@ -54,6 +92,34 @@ fn get_ideal_proportions(timestamp: u64) -> HashMap<String, f64> {
     ideal_proportions
 }
 
+fn get_tags(query: &HashMap<String, String>, timestamp: u64) -> Registry {
+    let mut tags = Registry::load("/home/sam/rofi_time_tracker/tags");
+    let palette = match query.get("palette") {
+        Some(name) => Palette::from_name(name),
+        None => Palette::Categorical,
+    };
+    tags.set_palette(
+        palette,
+        get_ideal_proportions(timestamp).into_keys().collect(),
+    );
+
+    tags
+}
+
+fn get_theme(query: &HashMap<String, String>) -> Theme {
+    match query.get("theme") {
+        Some(name) => Theme::from_name(name),
+        None => Theme::dark(),
+    }
+}
+
+fn get_chrome(query: &HashMap<String, String>) -> Chrome {
+    match query.get("chrome") {
+        Some(name) => Chrome::from_name(name),
+        None => Chrome::default(),
+    }
+}
+
 async fn index(mut _req: Request<()>) -> tide::Result {
     let output = include_str!("template.html");
     let mut res: Response = output.into();
@@ -72,7 +138,11 @@ async fn stats(req: Request<()>) -> tide::Result {
 
     let ideal_proportions = get_ideal_proportions(start_time);
 
-    let out = render_table(start_time, end_time, &ideal_proportions);
+    let tags = get_tags(&query, start_time);
+
+    let theme = get_theme(&query);
+
+    let out = render_table(start_time, end_time, &ideal_proportions, &tags, &theme);
     Ok(out.into())
 }
 
@@ -87,12 +157,33 @@ async fn timeline(req: Request<()>) -> tide::Result {
         .as_secs();
 
     let ideal_proportions = get_ideal_proportions(now);
+    let tags = get_tags(&query, now);
+    let theme = get_theme(&query);
+
+    let options = TimelineOptions {
+        width: width.parse::<f64>().unwrap(),
+        height: height.parse::<f64>().unwrap(),
+        start: match query.get("start_time") {
+            Some(x) => x.parse::<u64>().unwrap(),
+            None => 1672552800,
+        },
+        end: match query.get("end_time") {
+            Some(x) => x.parse::<u64>().unwrap(),
+            None => now,
+        },
+        granularity: match query.get("granularity") {
+            Some(name) => Granularity::from_name(name),
+            None => Granularity::Day,
+        },
+        column_width: query.get("column_width").map(|x| x.parse::<f64>().unwrap()),
+    };
 
     Ok(draw_timeline(
         &ideal_proportions,
-        "/home/sam/rofi_time_tracker/log",
-        width.parse::<f64>().unwrap(),
-        height.parse::<f64>().unwrap(),
+        &tags,
+        &theme,
+        &get_chrome(&query),
+        &options,
     )
     .into())
 }
@@ -111,7 +202,40 @@ async fn sankey(req: Request<()>) -> tide::Result {
 
     let ideal_proportions = get_ideal_proportions(start_time);
 
-    let out = render_sankey(start_time, end_time, width, height, &ideal_proportions);
+    let tags = get_tags(&query, start_time);
+
+    let theme = get_theme(&query);
+
+    let options = SankeyOptions {
+        width,
+        height,
+        order: match query.get("order") {
+            Some(name) => Order::from_name(name),
+            None => Order::Value,
+        },
+        labels: match query.get("labels") {
+            Some(name) => LabelMode::from_name(name),
+            None => LabelMode::Truncate,
+        },
+        threshold: match query.get("threshold") {
+            Some(name) => Threshold::from_name(name),
+            None => Threshold::Seconds(0.),
+        },
+        chart: match query.get("chart") {
+            Some(name) => Chart::from_name(name),
+            None => Chart::Sankey,
+        },
+        chrome: get_chrome(&query),
+    };
+
+    let out = render_sankey(
+        start_time,
+        end_time,
+        &ideal_proportions,
+        &tags,
+        &theme,
+        &options,
+    );
     Ok(out.into())
 }
 
@@ -127,17 +251,303 @@ async fn band(req: Request<()>) -> tide::Result {
     let width = width.parse::<f64>().unwrap();
     let height = height.parse::<f64>().unwrap();
 
-    let out = render_band(start_time, end_time, width, height);
+    let tags = get_tags(&query, start_time);
+
+    let theme = get_theme(&query);
+
+    let rows = query.get("mode").and_then(|x| Rows::from_name(x));
+
+    let out = match rows {
+        Some(rows) => render_gantt(
+            start_time,
+            end_time,
+            &tags,
+            &theme,
+            &get_chrome(&query),
+            &GanttOptions {
+                width,
+                height,
+                rows,
+            },
+        ),
+        None => render_band(
+            start_time,
+            end_time,
+            width,
+            height,
+            &tags,
+            &theme,
+            &get_chrome(&query),
+        ),
+    };
+    Ok(out.into())
+}
+
+async fn plan(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+    let width = query.get("width").unwrap();
+    let height = query.get("height").unwrap();
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+    let width = width.parse::<f64>().unwrap();
+    let height = height.parse::<f64>().unwrap();
+
+    let tags = get_tags(&query, start_time);
+
+    let theme = get_theme(&query);
+
+    let out = render_plan(start_time, end_time, width, height, &tags, &theme);
+    Ok(out.into())
+}
+
+async fn aliases(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+
+    let out = render_aliases(start_time, end_time);
+    Ok(out.into())
+}
+
+async fn overlaps(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+
+    let out = render_overlaps(start_time, end_time);
+    Ok(out.into())
+}
+
+async fn idle(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+
+    let out = render_idle(start_time, end_time);
+    Ok(out.into())
+}
+
+async fn compare(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+    let width = query.get("width").unwrap();
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+    let width = width.parse::<f64>().unwrap();
+
+    // Defaults to the period of the same length just before.
+    let previous_start = match query.get("previous_start") {
+        Some(x) => x.parse::<u64>().unwrap(),
+        None => start_time - (end_time - start_time),
+    };
+    let previous_end = match query.get("previous_end") {
+        Some(x) => x.parse::<u64>().unwrap(),
+        None => start_time,
+    };
+
+    let tags = get_tags(&query, start_time);
+
+    let theme = get_theme(&query);
+
+    let out = render_compare(
+        start_time,
+        end_time,
+        previous_start,
+        previous_end,
+        width,
+        &tags,
+        &theme,
+    );
+    Ok(out.into())
+}
+
+async fn heatmap(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+    // Shades by points unless a category is given.
+    let category = query
+        .get("category")
+        .map(|x| x.as_str())
+        .filter(|x| !x.is_empty());
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+
+    let ideal_proportions = get_ideal_proportions(end_time);
+
+    let tags = get_tags(&query, end_time);
+
+    let theme = get_theme(&query);
+
+    let out = render_heatmap(
+        start_time,
+        end_time,
+        &ideal_proportions,
+        category,
+        &tags,
+        &theme,
+    );
+    Ok(out.into())
+}
+
+async fn stream(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+    let width = query.get("width").unwrap();
+    let height = query.get("height").unwrap();
+    let show_ideal = query.get("ideal").is_some_and(|x| x == "true");
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+    let width = width.parse::<f64>().unwrap();
+    let height = height.parse::<f64>().unwrap();
+
+    let ideal_proportions = get_ideal_proportions(start_time);
+
+    let tags = get_tags(&query, start_time);
+
+    let theme = get_theme(&query);
+
+    let options = StreamOptions {
+        width,
+        height,
+        step: match query.get("step") {
+            Some(name) => Step::from_name(name),
+            None => Step::Day,
+        },
+    };
+
+    let out = render_streamgraph(
+        start_time,
+        end_time,
+        show_ideal.then_some(&ideal_proportions),
+        &tags,
+        &theme,
+        &options,
+    );
     Ok(out.into())
 }
 
+async fn distribution(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+    let width = query.get("width").unwrap();
+    // Splits the given category into its subcategories instead of showing
+    // every top-level category.
+    let category = query
+        .get("category")
+        .map(|x| x.as_str())
+        .filter(|x| !x.is_empty());
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+    let width = width.parse::<f64>().unwrap();
+
+    let tags = get_tags(&query, start_time);
+
+    let theme = get_theme(&query);
+
+    let out = render_distribution(start_time, end_time, category, width, &tags, &theme);
+    Ok(out.into())
+}
+
+async fn bullet(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+    let width = query.get("width").unwrap();
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+    let width = width.parse::<f64>().unwrap();
+
+    let ideal_proportions = get_ideal_proportions(start_time);
+
+    let tags = get_tags(&query, start_time);
+
+    let theme = get_theme(&query);
+
+    let out = render_bullet(
+        start_time,
+        end_time,
+        &ideal_proportions,
+        width,
+        &tags,
+        &theme,
+    );
+    Ok(out.into())
+}
+
+async fn calendar(req: Request<()>) -> tide::Result {
+    let query = req.query::<HashMap<String, String>>()?;
+    let start_time = query.get("start_time").unwrap();
+    let end_time = query.get("end_time").unwrap();
+    let merge = query.get("merge").is_some_and(|x| x == "true");
+
+    let start_time = start_time.parse::<u64>().unwrap();
+    let end_time = end_time.parse::<u64>().unwrap();
+
+    let (_, _, activities) = load_activities(start_time, end_time);
+
+    let mut res: Response = render_calendar(&activities, merge).into();
+    res.set_content_type("text/calendar");
+
+    Ok(res)
+}
+
 #[async_std::main]
 async fn main() -> tide::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && args[1] == "calendar" {
+        if args.len() < 4 {
+            eprintln!(
+                "usage: {} calendar <start_time> <end_time> [--merge]",
+                args[0]
+            );
+            std::process::exit(1);
+        }
+
+        let start_time = args[2].parse::<u64>().unwrap();
+        let end_time = args[3].parse::<u64>().unwrap();
+        let merge = args.iter().any(|x| x == "--merge");
+
+        let (_, _, activities) = load_activities(start_time, end_time);
+        print!("{}", render_calendar(&activities, merge));
+        return Ok(());
+    }
+
     let mut app = tide::new();
     app.at("/sankey").get(sankey);
     app.at("/stats").get(stats);
     app.at("/band").get(band);
+    app.at("/plan").get(plan);
     app.at("/timeline").get(timeline);
+    app.at("/overlaps").get(overlaps);
+    app.at("/aliases").get(aliases);
+    app.at("/idle").get(idle);
+    app.at("/compare").get(compare);
+    app.at("/heatmap").get(heatmap);
+    app.at("/stream").get(stream);
+    app.at("/distribution").get(distribution);
+    app.at("/bullet").get(bullet);
+    app.at("/calendar.ics").get(calendar);
     app.at("/").get(index);
     app.at("/").serve_dir("static/")?;
     app.listen("0.0.0.0:8723").await?;
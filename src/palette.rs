@@ -0,0 +1,160 @@
+#[derive(Clone, Copy)]
+pub enum Palette {
+    Categorical,
+    Colorblind,
+    HighContrast,
+    Light,
+    Dark,
+}
+
+impl Palette {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "colorblind" => Palette::Colorblind,
+            "high-contrast" => Palette::HighContrast,
+            "light" => Palette::Light,
+            "dark" => Palette::Dark,
+            _ => Palette::Categorical,
+        }
+    }
+
+    pub fn colors(&self) -> &'static [&'static str] {
+        match self {
+            // Tableau 10
+            Palette::Categorical => &[
+                "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1",
+                "#ff9da7", "#9c755f", "#bab0ac",
+            ],
+            // Okabe-Ito
+            Palette::Colorblind => &[
+                "#e69f00", "#56b4e9", "#009e73", "#f0e442", "#0072b2", "#d55e00", "#cc79a7",
+                "#999999",
+            ],
+            Palette::HighContrast => &[
+                "#ffff00", "#00ffff", "#ff00ff", "#00ff00", "#ff8000", "#0080ff", "#ff0000",
+                "#ffffff",
+            ],
+            Palette::Light => &[
+                "#1b6ca8", "#c0392b", "#27ae60", "#8e44ad", "#d35400", "#16a085", "#7f8c8d",
+                "#2c3e50",
+            ],
+            Palette::Dark => &[
+                "#8ecae6", "#ffb703", "#fb8500", "#90be6d", "#f28482", "#cdb4db", "#84a59d",
+                "#f6bd60",
+            ],
+        }
+    }
+
+    // Known categories get consecutive palette entries so that they never share
+    // a color. Anything else is placed by a stable hash of its name.
+    pub fn color(&self, category: &str, order: &[String]) -> String {
+        let colors = self.colors();
+        let index = match order.iter().position(|x| x == category) {
+            Some(i) => i,
+            None => stable_hash(category) as usize,
+        };
+
+        colors[index % colors.len()].to_string()
+    }
+}
+
+// FNV-1a, which unlike DefaultHasher does not change between Rust releases.
+pub fn stable_hash(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+fn hex_to_hsl(color: &str) -> Option<(f64, f64, f64)> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let r = channel(0)? as f64 / 255.;
+    let g = channel(2)? as f64 / 255.;
+    let b = channel(4)? as f64 / 255.;
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.;
+    let d = max - min;
+
+    if d == 0. {
+        return Some((0., 0., l));
+    }
+
+    let s = d / (1. - (2. * l - 1.).abs());
+    let h = match max {
+        x if x == r => 60. * ((g - b) / d).rem_euclid(6.),
+        x if x == g => 60. * ((b - r) / d + 2.),
+        _ => 60. * ((r - g) / d + 4.),
+    };
+
+    Some((h, s, l))
+}
+
+// Derives a lighter or darker variant of an ancestor's color for the node
+// reached through the given path below it. The offsets depend only on the
+// names so a node gets the same shade in every chart. Each level moves the
+// lightness by a quarter to a whole step, never zero, and turns the hue a
+// little, so siblings rarely match and a child never matches its parent.
+// Colors that are not hex codes are passed through unchanged.
+pub fn shade(color: &str, path: &[&str]) -> String {
+    if path.is_empty() {
+        return color.to_string();
+    }
+
+    let (mut h, s, mut l) = match hex_to_hsl(color) {
+        Some(x) => x,
+        None => return color.to_string(),
+    };
+
+    let mut step = 0.12;
+    for name in path {
+        // Two draws in [-1, 1) from independent bits of the hash.
+        let hash = stable_hash(name);
+        let lightness = (hash % 1000) as f64 / 500. - 1.;
+        let hue = ((hash >> 32) % 1000) as f64 / 500. - 1.;
+
+        l += lightness.signum() * (0.25 + 0.75 * lightness.abs()) * step;
+        h = (h + 150. * hue * step).rem_euclid(360.);
+        step /= 2.;
+    }
+    let l = l.clamp(0.15, 0.85);
+
+    format!("hsl({:.0}, {:.0}%, {:.0}%)", h, s * 100., l * 100.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_never_match_their_parent() {
+        let parent = shade("#4e79a7", &["code"]);
+        let names = [
+            "rust", "review", "python", "docs", "meet", "standup", "chess", "run",
+        ];
+
+        let mut shades: Vec<String> = names
+            .iter()
+            .map(|x| shade("#4e79a7", &["code", x]))
+            .collect();
+        assert!(shades.iter().all(|x| *x != parent));
+
+        shades.sort();
+        shades.dedup();
+        assert_eq!(shades.len(), names.len());
+    }
+
+    #[test]
+    fn non_ascii_colors_are_passed_through() {
+        assert_eq!(shade("#ééé", &["code"]), "#ééé");
+    }
+}
@@ -1,22 +1,50 @@
+use crate::aliases::{apply_aliases, Alias, AliasMatches};
+use crate::idle::{is_untracked, IdleRules};
 use crate::TreeNode;
-use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 
-fn parse_line(line: &str) -> (u64, String) {
+pub type Activity = (u64, i64, String);
+
+fn parse_line(line: &str, aliases: &[Alias]) -> (u64, String, Option<usize>) {
     let mut words = line.split('\t');
     let epoch: u64 = words.next().unwrap().parse().unwrap();
-    let tag = words.next().unwrap();
+    let (tag, alias) = apply_aliases(words.next().unwrap(), aliases);
+
+    (epoch, tag, alias)
+}
+
+pub fn build_tree(activities: &[Activity]) -> TreeNode {
+    let mut tree = TreeNode::new();
+
+    for activity in activities {
+        let time = activity.1 as f64;
+        let major = activity.2.split('.').next().unwrap();
+        let minor = activity.2.split('.').nth(1).unwrap();
+        let activity = activity.2.split('.').nth(2).unwrap();
+        tree.insert(major, minor, activity, time);
+    }
 
-    (epoch, tag.to_string())
+    tree
 }
 
+// Besides the sessions in the range, returns the start of the last session in
+// the file when it is still running at the end of the range.
 pub fn parse_file(
     filename: &str,
     begin_timestamp: u64,
     end_timestamp: u64,
-) -> (TreeNode, [String; 3], Vec<(u64, i64, String)>) {
+    aliases: &[Alias],
+    idle: &IdleRules,
+) -> (
+    TreeNode,
+    [String; 3],
+    Vec<Activity>,
+    AliasMatches,
+    Option<u64>,
+) {
     let mut activities = Vec::new();
+    let mut matches = vec![(0, 0); aliases.len()];
 
     let mut file = File::open(filename).unwrap();
     let mut contents = String::new();
@@ -24,26 +52,28 @@ pub fn parse_file(
     contents += format!("{}\tnow.now.now", end_timestamp).as_str();
     let mut lines = contents.lines();
 
-    let mut last_line = parse_line(lines.next().unwrap());
+    let mut last_line = parse_line(lines.next().unwrap(), aliases);
 
-    while let Some(line) = lines.next() {
-        let contents = parse_line(line);
-        let mut start_time = last_line.0;
-        let mut end_time = contents.0;
-        let activity = last_line.1.clone();
+    for line in lines {
+        let contents = parse_line(line, aliases);
 
-        if start_time < begin_timestamp {
-            start_time = begin_timestamp;
-        }
+        // Sessions are split before clipping so the limits apply to the whole
+        // session and not only the part inside the range.
+        for (start_time, end_time, activity) in idle.split(last_line.0, contents.0, &last_line.1) {
+            let start_time = start_time.max(begin_timestamp);
+            let end_time = end_time.min(end_timestamp);
 
-        if end_time > end_timestamp {
-            end_time = end_timestamp;
-        }
+            let delta = end_time as i64 - start_time as i64;
 
-        let delta = end_time as i64 - start_time as i64;
+            if delta > 0 && last_line.1 != "health.rest.sleep" {
+                // Only the part that keeps the renamed tag counts as a match.
+                if let (Some(alias), false) = (last_line.2, is_untracked(&activity)) {
+                    matches[alias].0 += 1;
+                    matches[alias].1 += delta;
+                }
 
-        if delta > 0 && activity != "health.rest.sleep" {
-            activities.push((start_time, delta, activity));
+                activities.push((start_time, delta, activity));
+            }
         }
 
         if contents.1 != "now.now.now" {
@@ -51,26 +81,61 @@ pub fn parse_file(
         }
     }
 
-    let mut tree = TreeNode {
-        value: 0.0,
-        children: HashMap::new(),
-    };
-
-    for activity in &activities {
-        let time = activity.1 as f64;
-        let major = activity.2.split('.').nth(0).unwrap();
-        let minor = activity.2.split('.').nth(1).unwrap();
-        let activity = activity.2.split('.').nth(2).unwrap();
-        tree.insert(major, minor, activity, time);
-    }
-
     (
-        tree,
+        build_tree(&activities),
         [
-            last_line.1.split('.').nth(0).unwrap().to_string(),
+            last_line.1.split('.').next().unwrap().to_string(),
             last_line.1.split('.').nth(1).unwrap().to_string(),
             last_line.1.split('.').nth(2).unwrap().to_string(),
         ],
         activities,
+        matches,
+        (last_line.0 < end_timestamp).then_some(last_line.0),
     )
 }
+
+// Whole sessions that overlap the range, before idle rules and without
+// clipping the start. The log is sorted, so the lines before the session
+// running at the start of the range are skipped on their timestamp alone.
+pub fn parse_sessions(
+    filename: &str,
+    begin_timestamp: u64,
+    end_timestamp: u64,
+    aliases: &[Alias],
+) -> Vec<Activity> {
+    let mut file = File::open(filename).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+
+    let lines: Vec<&str> = contents.lines().collect();
+    if lines.is_empty() {
+        return Vec::new();
+    }
+
+    let timestamp = |line: &str| -> u64 { line.split('\t').next().unwrap().parse().unwrap() };
+    let first = lines
+        .partition_point(|x| timestamp(x) <= begin_timestamp)
+        .saturating_sub(1);
+
+    let now = format!("{}\tnow.now.now", end_timestamp);
+    let mut sessions = Vec::new();
+    let mut last_line = parse_line(lines[first], aliases);
+    for line in lines[first + 1..].iter().copied().chain([now.as_str()]) {
+        if last_line.0 >= end_timestamp {
+            break;
+        }
+
+        let contents = parse_line(line, aliases);
+        let end_time = contents.0.min(end_timestamp);
+        if end_time > begin_timestamp
+            && end_time > last_line.0
+            && last_line.1 != "health.rest.sleep"
+        {
+            sessions.push((last_line.0, (end_time - last_line.0) as i64, last_line.1));
+        }
+
+        last_line = contents;
+    }
+
+    sessions
+}
@@ -0,0 +1,272 @@
+use crate::parse::Activity;
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::America::Chicago;
+use chrono_tz::Tz;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+
+// A time as written in the file, in the given zone or in UTC when it ends in
+// "Z", so that recurrences can be stepped in local time across DST changes.
+#[derive(Clone, Copy)]
+struct Time {
+    local: NaiveDateTime,
+    tz: Option<Tz>,
+}
+
+impl Time {
+    fn timestamp(&self) -> u64 {
+        match self.tz {
+            Some(tz) => tz
+                .from_local_datetime(&self.local)
+                .earliest()
+                .unwrap()
+                .timestamp() as u64,
+            None => Utc.from_utc_datetime(&self.local).timestamp() as u64,
+        }
+    }
+
+    fn on(&self, date: NaiveDate) -> Time {
+        Time {
+            local: date.and_time(self.local.time()),
+            tz: self.tz,
+        }
+    }
+}
+
+fn parse_time(name: &str, value: &str) -> Time {
+    // Zones that are not IANA names, such as the Windows names Outlook writes,
+    // are read as local time.
+    let tz = name
+        .split(';')
+        .find_map(|x| x.strip_prefix("TZID="))
+        .and_then(|x| x.trim_matches('"').parse::<Tz>().ok())
+        .unwrap_or(Chicago);
+
+    if let Some(value) = value.strip_suffix('Z') {
+        return Time {
+            local: NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").unwrap(),
+            tz: None,
+        };
+    }
+
+    let local = match value.len() {
+        8 => NaiveDate::parse_from_str(value, "%Y%m%d")
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap(),
+        _ => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").unwrap(),
+    };
+
+    Time {
+        local,
+        tz: Some(tz),
+    }
+}
+
+// Summaries are expected to be tags. Shorter summaries such as "work" are
+// padded so that they still land under the right category.
+fn summary_to_tag(summary: &str) -> String {
+    let mut parts: Vec<&str> = summary.split('.').collect();
+    while parts.len() < 3 {
+        parts.push("planned");
+    }
+
+    parts[..3].join(".")
+}
+
+#[derive(Default)]
+struct Event {
+    uid: String,
+    start: Option<Time>,
+    end: Option<Time>,
+    summary: Option<String>,
+    rrule: Option<String>,
+    exdates: Vec<u64>,
+    recurrence_id: Option<u64>,
+}
+
+const WEEKDAYS: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+
+// Start times of the occurrences of a daily or weekly rule that begin before
+// the end, honoring INTERVAL, COUNT, UNTIL and BYDAY. Other rules only keep
+// their first occurrence.
+fn expand(start: Time, rrule: &str, end_timestamp: u64) -> Vec<u64> {
+    let mut freq = "";
+    let mut interval = 1;
+    let mut count = None;
+    let mut until = None;
+    let mut weekdays = Vec::new();
+    for part in rrule.split(';') {
+        match part.split_once('=') {
+            Some(("FREQ", x)) => freq = x,
+            Some(("INTERVAL", x)) => interval = x.parse::<u64>().unwrap().max(1),
+            Some(("COUNT", x)) => count = Some(x.parse::<usize>().unwrap()),
+            Some(("UNTIL", x)) => until = Some(parse_time("UNTIL", x).timestamp()),
+            Some(("BYDAY", x)) => {
+                weekdays = x
+                    .split(',')
+                    .filter_map(|day| WEEKDAYS.iter().position(|x| day.ends_with(x)))
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+    weekdays.sort();
+
+    let first = start.local.date();
+    let (step, days) = match freq {
+        "DAILY" => (interval, vec![0]),
+        "WEEKLY" => {
+            if weekdays.is_empty() {
+                weekdays.push(first.weekday().num_days_from_monday() as usize);
+            }
+            (7 * interval, weekdays)
+        }
+        _ => return vec![start.timestamp()],
+    };
+
+    // Weekly rules are stepped from the Monday of the first week.
+    let mut period = match freq {
+        "WEEKLY" => first - Days::new(first.weekday().num_days_from_monday() as u64),
+        _ => first,
+    };
+
+    let mut starts = Vec::new();
+    loop {
+        for day in &days {
+            let date = period + Days::new(*day as u64);
+            if date < first {
+                continue;
+            }
+
+            let timestamp = start.on(date).timestamp();
+            if timestamp >= end_timestamp
+                || until.is_some_and(|x| timestamp > x)
+                || count.is_some_and(|x| starts.len() >= x)
+            {
+                return starts;
+            }
+            starts.push(timestamp);
+        }
+
+        period = period + Days::new(step);
+    }
+}
+
+pub fn parse_plan(filename: &str, begin_timestamp: u64, end_timestamp: u64) -> Vec<Activity> {
+    let mut file = File::open(filename).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+
+    // Long lines are folded onto continuation lines starting with whitespace.
+    let contents = contents
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut events = Vec::new();
+    let mut event = Event::default();
+    for line in contents.lines() {
+        let (name, value) = match line.split_once(':') {
+            Some(x) => x,
+            None => continue,
+        };
+
+        match name.split(';').next().unwrap() {
+            "BEGIN" if value == "VEVENT" => event = Event::default(),
+            "UID" => event.uid = value.to_string(),
+            "DTSTART" => event.start = Some(parse_time(name, value)),
+            "DTEND" => event.end = Some(parse_time(name, value)),
+            "SUMMARY" => event.summary = Some(value.replace("\\,", ",").replace("\\;", ";")),
+            "RRULE" => event.rrule = Some(value.to_string()),
+            "EXDATE" => event
+                .exdates
+                .extend(value.split(',').map(|x| parse_time(name, x).timestamp())),
+            "RECURRENCE-ID" => event.recurrence_id = Some(parse_time(name, value).timestamp()),
+            "END" if value == "VEVENT" => events.push(std::mem::take(&mut event)),
+            _ => {}
+        }
+    }
+
+    // Occurrences that were moved or edited are listed again as their own
+    // event with a RECURRENCE-ID, which replaces the generated one.
+    let replaced: HashSet<(&str, u64)> = events
+        .iter()
+        .filter_map(|x| x.recurrence_id.map(|id| (x.uid.as_str(), id)))
+        .collect();
+
+    let mut blocks = Vec::new();
+    for event in &events {
+        let (start, end, summary) = match (event.start, event.end, &event.summary) {
+            (Some(start), Some(end), Some(summary)) => (start, end, summary),
+            _ => continue,
+        };
+        let length = end.timestamp().saturating_sub(start.timestamp());
+
+        let starts = match (&event.rrule, event.recurrence_id) {
+            (Some(rrule), None) => expand(start, rrule, end_timestamp)
+                .into_iter()
+                .filter(|x| !event.exdates.contains(x))
+                .filter(|x| !replaced.contains(&(event.uid.as_str(), *x)))
+                .collect(),
+            _ => vec![start.timestamp()],
+        };
+
+        for start in starts {
+            let start_time = start.max(begin_timestamp);
+            let end_time = (start + length).min(end_timestamp);
+
+            let delta = end_time as i64 - start_time as i64;
+
+            if delta > 0 {
+                blocks.push((start_time, delta, summary_to_tag(summary)));
+            }
+        }
+    }
+
+    blocks.sort_by_key(|x| x.0);
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(value: &str) -> Time {
+        parse_time("DTSTART;TZID=America/Chicago", value)
+    }
+
+    #[test]
+    fn weekly_rules_repeat_on_each_day_in_local_time() {
+        // Starts on a Monday, spans the end of DST on November 1st.
+        let start = time("20261026T090000");
+        let starts = expand(start, "FREQ=WEEKLY;BYDAY=MO,WE;COUNT=4", u64::MAX);
+
+        let expected: Vec<u64> = ["20261026", "20261028", "20261102", "20261104"]
+            .iter()
+            .map(|x| time(&format!("{x}T090000")).timestamp())
+            .collect();
+        assert_eq!(starts, expected);
+    }
+
+    #[test]
+    fn rules_stop_at_until_and_at_the_end() {
+        let start = time("20261026T090000");
+        let until = time("20261029T090000").timestamp();
+
+        let starts = expand(start, "FREQ=DAILY;UNTIL=20261029T140000Z", u64::MAX);
+        assert_eq!(starts.len(), 4);
+        assert_eq!(*starts.last().unwrap(), until);
+
+        let starts = expand(start, "FREQ=DAILY;INTERVAL=2", until);
+        assert_eq!(starts.len(), 2);
+    }
+
+    #[test]
+    fn unknown_zones_fall_back_to_local_time() {
+        let start = parse_time("DTSTART;TZID=Central Standard Time", "20261026T090000");
+        assert_eq!(start.timestamp(), time("20261026T090000").timestamp());
+    }
+}
@@ -1,20 +1,29 @@
-use crate::parse::parse_file;
+use crate::chrome::{percent_axis, Chrome};
+use crate::idle::is_untracked;
+use crate::layout::{Chart, SankeyOptions};
+use crate::parse::Activity;
+use crate::plan::parse_plan;
+use crate::sources::{
+    load_activities, load_alias_report, load_idle_report, load_sources, Overlap, Suspicious,
+};
+use crate::sunburst::render_sunburst;
+use crate::svg::{rect, svg, text, Element};
+use crate::tags::Registry;
+use crate::theme::Theme;
 use crate::tree::render_tree;
 use crate::tree_node::TreeNode;
-use crate::util::format_time;
+use crate::treemap::render_treemap;
+use crate::util::{escape, format_time};
 use chrono::{DateTime, TimeZone};
 use chrono_tz::America::Chicago;
-use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
+use std::path::Path;
 
 pub fn get_points(tree: &TreeNode, ideal_proportions: &HashMap<String, f64>) -> f64 {
-    let mut keys: Vec<&String> = tree.children.keys().into_iter().collect();
+    let mut keys: Vec<&String> = tree.children.keys().collect();
     keys.sort();
 
-    let time_domain = keys
-        .iter()
-        .fold(0., |acc, x| tree.children[x.as_str()].value + acc);
+    let time_domain = tree.value;
     let ideal_domain = ideal_proportions.iter().fold(0., |acc, x| acc + x.1);
 
     let mut points = 0.;
@@ -27,7 +36,7 @@ pub fn get_points(tree: &TreeNode, ideal_proportions: &HashMap<String, f64>) ->
         };
 
         ideal_value += 100. * value / ideal_domain;
-        let actual_value = 100. * tree.children[key].value / time_domain;
+        let actual_value = 100. * tree.value_at(key) / time_domain;
 
         match actual_value > ideal_value {
             false => {
@@ -48,33 +57,30 @@ pub fn render_table(
     start_timestamp: u64,
     end_timestamp: u64,
     ideal_proportions: &HashMap<String, f64>,
+    tags: &Registry,
+    theme: &Theme,
 ) -> String {
-    let (tree, current, _) = parse_file(
-        "/home/sam/rofi_time_tracker/log",
-        start_timestamp,
-        end_timestamp,
-    );
+    let (tree, current, _) = load_activities(start_timestamp, end_timestamp);
 
-    let mut out = String::from("<span>");
+    let mut out = format!(
+        "<span style='color: {}; font-family: {}'>",
+        escape(&theme.text),
+        escape(&theme.font_family)
+    );
     out += "<span class='stats-container'>";
 
-    out += format!("<span>Category</span>").as_str();
-    out += format!("<span>Actual</span>").as_str();
-    out += format!("<span>Ideal</span>").as_str();
-    out += format!("<span>Comp.</span>").as_str();
-    out += format!("<span>Pred.</span>").as_str();
-    out += format!("<span>Ratio</span>").as_str();
+    out += "<span>Category</span>";
+    out += "<span>Actual</span>";
+    out += "<span>Ideal</span>";
+    out += "<span>Comp.</span>";
+    out += "<span>Pred.</span>";
+    out += "<span>Ratio</span>";
 
-    let mut keys: Vec<&String> = ideal_proportions.keys().into_iter().collect();
+    let mut keys: Vec<&String> = ideal_proportions.keys().collect();
 
     keys.sort();
 
-    let time_domain = keys
-        .iter()
-        .fold(0., |acc, x| match tree.children.get(x.as_str()) {
-            Some(x) => x.value + acc,
-            None => acc,
-        });
+    let time_domain = keys.iter().fold(0., |acc, x| tree.value_at(x) + acc);
 
     let mut lines = Vec::new();
 
@@ -83,14 +89,21 @@ pub fn render_table(
             continue;
         }
 
-        let capital_key = key.chars().next().unwrap().to_uppercase().to_string() + &key[1..];
+        let capital_key = match tags.name(key) {
+            name if name == *key => {
+                key.chars().next().unwrap().to_uppercase().to_string() + &key[1..]
+            }
+            name => name,
+        };
+        let swatch = format!(
+            "<span style='color: {}' title='{}'>&#9632;</span>",
+            escape(&tags.color(key)),
+            escape(&tags.description(key).unwrap_or_default())
+        );
 
         let ideal_value = ideal_proportions[key];
 
-        let actual = match tree.children.get(key.as_str()) {
-            Some(x) => x.value,
-            None => 0.,
-        };
+        let actual = tree.value_at(key);
 
         let day_length = 12. * 60. * 60.;
 
@@ -112,7 +125,11 @@ pub fn render_table(
         let style = format!("font-weight: {}; color: {}", weight, color);
 
         let mut line = String::new();
-        line += format!("<span style='{style}'>{}</span>", capital_key).as_str();
+        line += format!(
+            "<span style='{style}'>{swatch} {}</span>",
+            escape(&capital_key)
+        )
+        .as_str();
         line += format!("<span style='{style}'>{:.3}%</span>", actual_value).as_str();
         line += format!("<span style='{style}'>{:.3}%</span>", ideal_value).as_str();
         line += format!("<span style='{style}'>{}</span>", completed).as_str();
@@ -136,59 +153,300 @@ pub fn render_table(
 pub fn render_sankey(
     start_timestamp: u64,
     end_timestamp: u64,
-    width: f64,
-    height: f64,
     ideal_proportions: &HashMap<String, f64>,
+    tags: &Registry,
+    theme: &Theme,
+    options: &SankeyOptions,
 ) -> String {
-    let (tree, current, _) = parse_file(
-        "/home/sam/rofi_time_tracker/log",
-        start_timestamp,
-        end_timestamp,
-    );
-
-    let svg = render_tree(&tree, width, height, current, ideal_proportions);
+    let (tree, current, _) = load_activities(start_timestamp, end_timestamp);
 
-    svg
+    match options.chart {
+        Chart::Sankey => render_tree(&tree, current, ideal_proportions, tags, theme, options),
+        Chart::Sunburst => render_sunburst(&tree, tags, theme, options),
+        Chart::Treemap => render_treemap(&tree, tags, theme, options),
+    }
 }
 
-pub fn render_band(start_timestamp: u64, end_timestamp: u64, width: f64, height: f64) -> String {
-    let (_, _, band) = parse_file(
-        "/home/sam/rofi_time_tracker/log",
-        start_timestamp,
-        end_timestamp,
-    );
+pub fn render_band(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    width: f64,
+    height: f64,
+    tags: &Registry,
+    theme: &Theme,
+    chrome: &Chrome,
+) -> String {
+    let (_, _, band) = load_activities(start_timestamp, end_timestamp);
 
     let len = band.len();
 
     let total = band.iter().fold(1, |acc, x| acc + x.1) as f64;
 
-    let mut svg = format!("<svg width='100%' height='100%' xmlns='http://www.w3.org/2000/svg'>\n");
-    let mut y = 0.;
-    let x = 0.;
-    for (timestamp, duration, name) in band {
-        let mut state = DefaultHasher::new();
-        name.split(".").nth(0).unwrap().to_string().hash(&mut state);
-        let hue = state.finish() % 360;
-        let color = format!("hsl({}, 30%, 50%)", hue);
+    let mut categories: Vec<String> = band
+        .iter()
+        .map(|x| x.2.split('.').next().unwrap().to_string())
+        .collect();
+    categories.sort();
+    categories.dedup();
 
-        let width = width;
-        let height = 0.9 * duration as f64 / total * height;
+    let area = chrome.area(width, height, &categories, true, false);
+
+    let mut svg = svg();
+    svg.push(theme.background());
+    let mut y = area.y;
+    let x = area.x;
+    for (timestamp, duration, name) in band {
+        let height = duration as f64 / total * area.height;
 
         let time: DateTime<_> = Chicago.timestamp_opt(timestamp as i64, 0).unwrap();
-        svg += format!(
-            "<rect class='hover-element' data-tooltip='{}<br>{}<br>{}' x='{}' y='{}' width='{}' height='{}' fill='{}' />\n",
-            name,
+        let tooltip = format!(
+            "{}<br>{}<br>{}",
+            escape(&tags.name(&name)),
+            time,
+            format_time(duration as u64)
+        );
+        svg.push(
+            rect(x, y, area.width, height)
+                .attr("class", "hover-element")
+                .attr("data-tooltip", tooltip)
+                .attr("fill", tags.color(&name)),
+        );
+
+        y += height;
+    }
+
+    if chrome.axes {
+        svg.push(percent_axis(&area, theme));
+    }
+    svg.extend(chrome.draw("Sessions", &categories, width, height, tags, theme));
+
+    svg.render() + format!("<div>{} context switches</div>", len).as_str()
+}
+
+pub fn render_overlaps(start_timestamp: u64, end_timestamp: u64) -> String {
+    let (_, _, _, overlaps) = load_sources(start_timestamp, end_timestamp);
+
+    let mut out = String::from("<div>");
+    out += format!("<div>{} overlaps</div>", overlaps.len()).as_str();
+
+    for overlap in overlaps {
+        out += overlap_line(&overlap).as_str();
+    }
+
+    out + "</div>"
+}
+
+fn overlap_line(overlap: &Overlap) -> String {
+    let time: DateTime<_> = Chicago.timestamp_opt(overlap.start as i64, 0).unwrap();
+    format!(
+        "<div>{time} for {}: kept {} ({}), dropped {} ({})</div>",
+        format_time(overlap.duration as u64),
+        escape(&overlap.kept.1),
+        escape(&overlap.kept.0),
+        escape(&overlap.dropped.1),
+        escape(&overlap.dropped.0)
+    )
+}
+
+fn draw_column(
+    activities: &[Activity],
+    x: f64,
+    width: f64,
+    start_timestamp: u64,
+    scale: f64,
+    tags: &Registry,
+) -> Vec<Element> {
+    let mut elements = Vec::new();
+    for (timestamp, duration, name) in activities {
+        let y = 20. + (timestamp - start_timestamp) as f64 * scale;
+        let height = *duration as f64 * scale;
+
+        let time: DateTime<_> = Chicago.timestamp_opt(*timestamp as i64, 0).unwrap();
+        let tooltip = format!(
+            "{}<br>{}<br>{}",
+            escape(&tags.name(name)),
             time,
-            format_time(duration as u64),
-            x, y, width, height, color
+            format_time(*duration as u64)
+        );
+        elements.push(
+            rect(x, y, width, height)
+                .attr("class", "hover-element")
+                .attr("data-tooltip", tooltip)
+                .attr("fill", tags.color(name)),
+        );
+    }
+
+    elements
+}
+
+pub fn render_plan(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    width: f64,
+    height: f64,
+    tags: &Registry,
+    theme: &Theme,
+) -> String {
+    let (_, _, actual) = load_activities(start_timestamp, end_timestamp);
+
+    let filename = "/home/sam/rofi_time_tracker/plan.ics";
+    let planned = match Path::new(filename).exists() {
+        true => parse_plan(filename, start_timestamp, end_timestamp),
+        false => Vec::new(),
+    };
+
+    let scale = 0.9 * (height - 20.) / (end_timestamp - start_timestamp).max(1) as f64;
+    let column_width = 0.35 * width;
+
+    let label = |x: f64, y: f64, content: &str| {
+        text(x, y, content)
+            .attr("font-family", &theme.font_family)
+            .attr("font-size", 12)
+            .attr("fill", &theme.text)
+    };
+
+    let mut svg = svg();
+    svg.push(theme.background());
+    svg.push(label(0., 14., "Planned"));
+    svg.push(label(column_width + 10., 14., "Actual"));
+    svg.extend(draw_column(
+        &planned,
+        0.,
+        column_width,
+        start_timestamp,
+        scale,
+        tags,
+    ));
+    svg.extend(draw_column(
+        &actual,
+        column_width + 10.,
+        column_width,
+        start_timestamp,
+        scale,
+        tags,
+    ));
+
+    let mut totals: HashMap<String, (i64, i64)> = HashMap::new();
+    for (_, duration, name) in &planned {
+        let category = name.split('.').next().unwrap().to_string();
+        totals.entry(category).or_insert((0, 0)).0 += duration;
+    }
+    // Untracked time has no plan to be compared with.
+    for (_, duration, name) in actual.iter().filter(|x| !is_untracked(&x.2)) {
+        let category = name.split('.').next().unwrap().to_string();
+        totals.entry(category).or_insert((0, 0)).1 += duration;
+    }
+
+    let mut keys: Vec<&String> = totals.keys().collect();
+    keys.sort();
+
+    let x = 2. * column_width + 30.;
+    svg.push(label(x, 14., "Planned / Actual"));
+
+    let mut y = 34.;
+    for key in keys {
+        let (planned, actual) = totals[key];
+
+        let color = match actual >= planned {
+            false => "red",
+            true => "green",
+        };
+
+        let content = format!(
+            "{}: {} / {}",
+            tags.name(key),
+            format_time(planned as u64),
+            format_time(actual as u64)
+        );
+        svg.push(label(x, y, &content).attr("fill", color));
+        y += 16.;
+    }
+
+    svg.render()
+}
+
+pub fn render_aliases(start_timestamp: u64, end_timestamp: u64) -> String {
+    let report = load_alias_report(start_timestamp, end_timestamp);
+
+    let mut out = String::from("<div>");
+    for (alias, count, duration) in report {
+        if count == 0 {
+            continue;
+        }
+
+        out += format!(
+            "<div>{} &rarr; {}: {count} sessions, {}</div>",
+            escape(&alias.from),
+            escape(&alias.to),
+            format_time(duration as u64)
         )
         .as_str();
+    }
 
-        y += height;
+    out + "</div>"
+}
+
+pub fn render_idle(start_timestamp: u64, end_timestamp: u64) -> String {
+    let report = load_idle_report(start_timestamp, end_timestamp);
+
+    let mut out = String::from("<div>");
+    out += format!("<div>{} suspicious sessions</div>", report.len()).as_str();
+
+    for session in report {
+        out += idle_line(&session).as_str();
+    }
+
+    out + "</div>"
+}
+
+fn idle_line(session: &Suspicious) -> String {
+    let time: DateTime<_> = Chicago.timestamp_opt(session.start as i64, 0).unwrap();
+    format!(
+        "<div>{time} for {}: {} ({}), {} untracked</div>",
+        format_time(session.duration as u64),
+        escape(&session.tag),
+        escape(&session.source),
+        format_time(session.untracked as u64)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAG: &str = "work.o'neil.\"x\"<b>&co";
+
+    // Only the wrapping div may open a tag.
+    fn has_raw_markup(html: &str) -> bool {
+        html.matches('<').count() != 2 || html.contains('"') || html.contains('\'')
     }
-    svg += "</svg>";
 
-    svg += format!("<div>{} context switches</div>", len).as_str();
+    #[test]
+    fn overlap_lines_escape_tags() {
+        let overlap = Overlap {
+            start: 0,
+            duration: 60,
+            kept: ("laptop<".to_string(), TAG.to_string()),
+            dropped: ("desktop&".to_string(), TAG.to_string()),
+        };
+        let html = overlap_line(&overlap);
+
+        assert!(!has_raw_markup(&html));
+        assert!(html.contains(&escape(TAG)));
+    }
 
-    svg
+    #[test]
+    fn idle_lines_escape_tags() {
+        let session = Suspicious {
+            source: "laptop'".to_string(),
+            start: 0,
+            duration: 7200,
+            tag: TAG.to_string(),
+            untracked: 3600,
+        };
+        let html = idle_line(&session);
+
+        assert!(!has_raw_markup(&html));
+        assert!(html.contains(&escape(TAG)));
+    }
 }
@@ -0,0 +1,513 @@
+use crate::activitywatch::{parse_export, parse_rules};
+use crate::aliases::{parse_aliases, Alias};
+use crate::idle::{is_untracked, parse_idle_rules};
+use crate::parse::{build_tree, parse_file, parse_sessions, Activity};
+use crate::tree_node::TreeNode;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+pub enum Policy {
+    Latest,
+    Primary(String),
+}
+
+pub struct Source {
+    pub name: String,
+    pub path: String,
+}
+
+pub struct Overlap {
+    pub start: u64,
+    pub duration: i64,
+    pub kept: (String, String),
+    pub dropped: (String, String),
+}
+
+// The sources file lists one "<name> <path>" per line, optionally followed by a
+// "policy latest" or "policy primary <name>" line. Without it only the local
+// log is read.
+pub fn parse_sources(filename: &str) -> (Vec<Source>, Policy) {
+    let default_log = "/home/sam/rofi_time_tracker/log";
+    if !Path::new(filename).exists() {
+        return (
+            vec![Source {
+                name: "local".to_string(),
+                path: default_log.to_string(),
+            }],
+            Policy::Latest,
+        );
+    }
+
+    let mut file = File::open(filename).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+
+    let mut sources = Vec::new();
+    let mut policy = Policy::Latest;
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let words: Vec<&str> = line.split(' ').collect();
+        match words[..] {
+            ["policy", "latest"] => policy = Policy::Latest,
+            ["policy", "primary", name] => policy = Policy::Primary(name.to_string()),
+            [name, path] => sources.push(Source {
+                name: name.to_string(),
+                path: path.to_string(),
+            }),
+            _ => panic!("Invalid sources file"),
+        }
+    }
+
+    // A policy alone leaves nothing to load.
+    if sources.is_empty() {
+        panic!("Invalid sources file");
+    }
+
+    (sources, policy)
+}
+
+// Splits the range into segments at every session boundary and, for each
+// segment, keeps the session chosen by the policy. "Latest" keeps whichever
+// session started most recently, "primary" keeps the primary machine's session
+// and falls back to the latest one where the primary has nothing. Tracked time
+// on any machine wins over untracked time.
+pub fn merge(
+    streams: &[(String, Vec<Activity>)],
+    policy: &Policy,
+) -> (Vec<Activity>, Vec<Overlap>) {
+    let mut intervals = Vec::new();
+    for (source, activities) in streams {
+        for (start, duration, tag) in activities {
+            intervals.push((*start, *start + *duration as u64, tag, source));
+        }
+    }
+    intervals.sort_by_key(|x| x.0);
+
+    let mut boundaries: Vec<u64> = intervals.iter().flat_map(|x| [x.0, x.1]).collect();
+    boundaries.sort();
+    boundaries.dedup();
+
+    let mut merged: Vec<Activity> = Vec::new();
+    let mut overlaps: Vec<Overlap> = Vec::new();
+    let mut last_winner = None;
+    let mut last_pairs: Vec<(usize, usize, usize)> = Vec::new();
+
+    let mut active: Vec<usize> = Vec::new();
+    let mut next = 0;
+    for window in boundaries.windows(2) {
+        let (begin, end) = (window[0], window[1]);
+
+        active.retain(|&i| intervals[i].1 > begin);
+        while next < intervals.len() && intervals[next].0 <= begin {
+            if intervals[next].1 > begin {
+                active.push(next);
+            }
+            next += 1;
+        }
+
+        if active.is_empty() {
+            last_winner = None;
+            last_pairs.clear();
+            continue;
+        }
+
+        let latest = *active
+            .iter()
+            .max_by_key(|&&i| (!is_untracked(intervals[i].2), intervals[i].0))
+            .unwrap();
+        let winner = match policy {
+            Policy::Latest => latest,
+            Policy::Primary(name) => *active
+                .iter()
+                .find(|&&i| intervals[i].3 == name && !is_untracked(intervals[i].2))
+                .unwrap_or(&latest),
+        };
+
+        let duration = (end - begin) as i64;
+        match last_winner == Some(winner) {
+            true => merged.last_mut().unwrap().1 += duration,
+            false => merged.push((begin, duration, intervals[winner].2.clone())),
+        }
+        last_winner = Some(winner);
+
+        let mut pairs = Vec::new();
+        for &i in &active {
+            if intervals[i].3 == intervals[winner].3 {
+                continue;
+            }
+
+            let index = match last_pairs.iter().find(|x| x.0 == winner && x.1 == i) {
+                Some(x) => {
+                    overlaps[x.2].duration += duration;
+                    x.2
+                }
+                None => {
+                    overlaps.push(Overlap {
+                        start: begin,
+                        duration,
+                        kept: (intervals[winner].3.clone(), intervals[winner].2.clone()),
+                        dropped: (intervals[i].3.clone(), intervals[i].2.clone()),
+                    });
+                    overlaps.len() - 1
+                }
+            };
+            pairs.push((winner, i, index));
+        }
+        last_pairs = pairs;
+    }
+
+    (merged, overlaps)
+}
+
+// Every log reports its last tag as running until the end of the range, even
+// on a machine that has not been used since. Such an open session is ended
+// where any other log next switches, so an idle machine neither outranks nor
+// fills the gaps of one in use.
+fn end_open_sessions(streams: &mut [(String, Vec<Activity>)], opens: &[Option<u64>]) {
+    // The switches of each log. The pieces of an open session are clipped to
+    // the range, so its real start is used instead.
+    let switches: Vec<Vec<u64>> = streams
+        .iter()
+        .zip(opens)
+        .map(|((_, activities), open)| {
+            let since = open.unwrap_or(u64::MAX);
+            activities
+                .iter()
+                .filter(|x| x.0 < since && !is_untracked(&x.2))
+                .map(|x| x.0)
+                .chain(*open)
+                .collect()
+        })
+        .collect();
+
+    for (i, (_, activities)) in streams.iter_mut().enumerate() {
+        let since = match opens[i] {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let cut = switches
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .flat_map(|(_, x)| x.iter())
+            .filter(|x| **x > since)
+            .min();
+
+        if let Some(&cut) = cut {
+            activities.retain(|x| x.0 < cut);
+            for activity in activities.iter_mut() {
+                let end = activity.0 + activity.1 as u64;
+                if end > cut {
+                    activity.1 = (cut - activity.0) as i64;
+                }
+            }
+        }
+    }
+}
+
+pub fn load_sources(
+    start_timestamp: u64,
+    end_timestamp: u64,
+) -> (TreeNode, [String; 3], Vec<Activity>, Vec<Overlap>) {
+    let (sources, policy) = parse_sources("/home/sam/rofi_time_tracker/sources");
+    let aliases = parse_aliases("/home/sam/rofi_time_tracker/aliases");
+    let idle = parse_idle_rules("/home/sam/rofi_time_tracker/idle");
+
+    let mut currents = Vec::new();
+    let mut streams = Vec::new();
+    let mut opens = Vec::new();
+    for source in sources {
+        let (_, current, activities, _, open) = parse_file(
+            &source.path,
+            start_timestamp,
+            end_timestamp,
+            &aliases,
+            &idle,
+        );
+        let last_switch = activities.last().map(|x| x.0).unwrap_or(0);
+        currents.push((source.name.clone(), current, last_switch));
+        streams.push((source.name, activities));
+        opens.push(open);
+    }
+
+    end_open_sessions(&mut streams, &opens);
+
+    // ActivityWatch events compete with the logs like any other machine.
+    let export = "/home/sam/rofi_time_tracker/activitywatch.json";
+    if Path::new(export).exists() {
+        let rules = parse_rules("/home/sam/rofi_time_tracker/activitywatch_rules");
+        let events = parse_export(export, &rules, start_timestamp, end_timestamp);
+        streams.push(("activitywatch".to_string(), events));
+    }
+
+    let (activities, overlaps) = merge(&streams, &policy);
+
+    let primary = match &policy {
+        Policy::Primary(name) => currents.iter().position(|x| &x.0 == name),
+        Policy::Latest => None,
+    };
+    let latest = (0..currents.len()).max_by_key(|&i| currents[i].2).unwrap();
+    let current = currents.swap_remove(primary.unwrap_or(latest)).1;
+
+    (build_tree(&activities), current, activities, overlaps)
+}
+
+// Counts, for each alias, how many sessions in the range it renamed and how
+// much time they cover.
+pub fn load_alias_report(start_timestamp: u64, end_timestamp: u64) -> Vec<(Alias, u64, i64)> {
+    let (sources, _) = parse_sources("/home/sam/rofi_time_tracker/sources");
+    let aliases = parse_aliases("/home/sam/rofi_time_tracker/aliases");
+    let idle = parse_idle_rules("/home/sam/rofi_time_tracker/idle");
+
+    let mut totals = vec![(0, 0); aliases.len()];
+    for source in sources {
+        let (_, _, _, matches, _) = parse_file(
+            &source.path,
+            start_timestamp,
+            end_timestamp,
+            &aliases,
+            &idle,
+        );
+        for (total, count) in totals.iter_mut().zip(matches) {
+            total.0 += count.0;
+            total.1 += count.1;
+        }
+    }
+
+    aliases
+        .into_iter()
+        .zip(totals)
+        .map(|(alias, total)| (alias, total.0, total.1))
+        .collect()
+}
+
+pub struct Suspicious {
+    pub source: String,
+    pub start: u64,
+    pub duration: i64,
+    pub tag: String,
+    pub untracked: i64,
+}
+
+// The longest sessions overlapping the range that break an idle rule, with how
+// much of each is moved to the untracked bucket. Sessions are reported whole,
+// including any part outside the range.
+pub fn load_idle_report(start_timestamp: u64, end_timestamp: u64) -> Vec<Suspicious> {
+    let (sources, _) = parse_sources("/home/sam/rofi_time_tracker/sources");
+    let aliases = parse_aliases("/home/sam/rofi_time_tracker/aliases");
+    let idle = parse_idle_rules("/home/sam/rofi_time_tracker/idle");
+
+    let mut report = Vec::new();
+    for source in sources {
+        let sessions = parse_sessions(&source.path, start_timestamp, end_timestamp, &aliases);
+        for (start, duration, tag) in sessions {
+            if !idle.is_suspicious(&tag, duration) {
+                continue;
+            }
+
+            let untracked = duration - idle.limit(&tag).unwrap();
+            report.push(Suspicious {
+                source: source.name.clone(),
+                start,
+                duration,
+                tag,
+                untracked,
+            });
+        }
+    }
+
+    report.sort_by_key(|x| -x.duration);
+    report.truncate(20);
+
+    report
+}
+
+// Loads every configured log and, when an ActivityWatch export is present, the
+// automatically tracked events mapped through the rules file, merged so each
+// minute is counted once.
+pub fn load_activities(
+    start_timestamp: u64,
+    end_timestamp: u64,
+) -> (TreeNode, [String; 3], Vec<Activity>) {
+    let (tree, current, activities, _) = load_sources(start_timestamp, end_timestamp);
+
+    (tree, current, activities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(name: &str, activities: &[(u64, i64, &str)]) -> (String, Vec<Activity>) {
+        (
+            name.to_string(),
+            activities
+                .iter()
+                .map(|(start, duration, tag)| (*start, *duration, tag.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn latest_keeps_the_most_recent_session() {
+        let streams = [
+            stream("laptop", &[(0, 100, "work.code.rust")]),
+            stream("desktop", &[(50, 20, "fun.games.chess")]),
+        ];
+
+        let (merged, overlaps) = merge(&streams, &Policy::Latest);
+
+        assert_eq!(
+            merged,
+            vec![
+                (0, 50, "work.code.rust".to_string()),
+                (50, 20, "fun.games.chess".to_string()),
+                (70, 30, "work.code.rust".to_string()),
+            ]
+        );
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].kept.0, "desktop");
+        assert_eq!((overlaps[0].start, overlaps[0].duration), (50, 20));
+    }
+
+    #[test]
+    fn primary_falls_back_where_it_has_nothing() {
+        let streams = [
+            stream("laptop", &[(0, 100, "work.code.rust")]),
+            stream("desktop", &[(50, 100, "fun.games.chess")]),
+        ];
+
+        let (merged, overlaps) = merge(&streams, &Policy::Primary("laptop".to_string()));
+
+        assert_eq!(
+            merged,
+            vec![
+                (0, 100, "work.code.rust".to_string()),
+                (100, 50, "fun.games.chess".to_string()),
+            ]
+        );
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(
+            overlaps[0].kept,
+            ("laptop".to_string(), "work.code.rust".to_string())
+        );
+        assert_eq!(
+            overlaps[0].dropped,
+            ("desktop".to_string(), "fun.games.chess".to_string())
+        );
+        assert_eq!((overlaps[0].start, overlaps[0].duration), (50, 50));
+    }
+
+    #[test]
+    fn adjacent_segments_of_one_session_are_coalesced() {
+        let streams = [
+            stream("laptop", &[(0, 100, "work.code.rust")]),
+            stream(
+                "desktop",
+                &[(10, 10, "fun.games.chess"), (40, 10, "learn.read.paper")],
+            ),
+        ];
+
+        let (merged, overlaps) = merge(&streams, &Policy::Primary("laptop".to_string()));
+
+        assert_eq!(merged, vec![(0, 100, "work.code.rust".to_string())]);
+        assert_eq!(overlaps.len(), 2);
+    }
+
+    #[test]
+    fn overlaps_accumulate_across_segments() {
+        let streams = [
+            stream("laptop", &[(0, 100, "work.code.rust")]),
+            stream("desktop", &[(10, 30, "fun.games.chess")]),
+            stream("phone", &[(20, 5, "learn.read.paper")]),
+        ];
+
+        let (_, overlaps) = merge(&streams, &Policy::Primary("laptop".to_string()));
+
+        let desktop = overlaps.iter().find(|x| x.dropped.0 == "desktop").unwrap();
+        assert_eq!((desktop.start, desktop.duration), (10, 30));
+        let phone = overlaps.iter().find(|x| x.dropped.0 == "phone").unwrap();
+        assert_eq!((phone.start, phone.duration), (20, 5));
+        assert_eq!(overlaps.len(), 2);
+    }
+
+    #[test]
+    fn tracked_time_wins_over_untracked_time() {
+        let streams = [
+            stream(
+                "laptop",
+                &[(0, 50, "work.code.rust"), (50, 50, "untracked.idle.work")],
+            ),
+            stream("desktop", &[(20, 60, "fun.games.chess")]),
+        ];
+
+        let (merged, _) = merge(&streams, &Policy::Latest);
+
+        assert_eq!(
+            merged,
+            vec![
+                (0, 20, "work.code.rust".to_string()),
+                (20, 60, "fun.games.chess".to_string()),
+                (80, 20, "untracked.idle.work".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn open_sessions_end_at_the_next_switch_elsewhere() {
+        let mut streams = [
+            stream("laptop", &[(0, 1000, "work.code.rust")]),
+            stream(
+                "desktop",
+                &[
+                    (100, 200, "fun.games.chess"),
+                    (500, 500, "learn.read.paper"),
+                ],
+            ),
+        ];
+
+        end_open_sessions(&mut streams, &[Some(0), Some(500)]);
+
+        assert_eq!(streams[0].1, vec![(0, 100, "work.code.rust".to_string())]);
+        assert_eq!(streams[1].1.len(), 2);
+
+        let (merged, overlaps) = merge(&streams, &Policy::Primary("laptop".to_string()));
+
+        assert_eq!(
+            merged,
+            vec![
+                (0, 100, "work.code.rust".to_string()),
+                (100, 200, "fun.games.chess".to_string()),
+                (500, 500, "learn.read.paper".to_string()),
+            ]
+        );
+        assert!(overlaps.is_empty());
+    }
+
+    #[test]
+    fn open_sessions_that_started_before_the_range_use_their_real_start() {
+        // The laptop was last switched before the range and the desktop within
+        // it, so only the laptop's session is stale.
+        let mut streams = [
+            stream("laptop", &[(100, 900, "work.code.rust")]),
+            stream("desktop", &[(600, 400, "fun.games.chess")]),
+        ];
+
+        end_open_sessions(&mut streams, &[Some(0), Some(600)]);
+
+        assert_eq!(streams[0].1, vec![(100, 500, "work.code.rust".to_string())]);
+        assert_eq!(
+            streams[1].1,
+            vec![(600, 400, "fun.games.chess".to_string())]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::sources::load_activities;
+use crate::svg::{path, svg, Element, PathData};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::util::{escape, next_hour, next_midnight, split_sessions, start_of_day};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Clone, Copy)]
+pub enum Step {
+    Hour,
+    Day,
+}
+
+impl Step {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "hour" => Step::Hour,
+            _ => Step::Day,
+        }
+    }
+
+    fn next(&self, timestamp: u64) -> u64 {
+        match self {
+            Step::Hour => next_hour(timestamp),
+            Step::Day => next_midnight(timestamp),
+        }
+    }
+
+    fn start(&self, timestamp: u64) -> u64 {
+        match self {
+            Step::Hour => timestamp / 3600 * 3600,
+            Step::Day => start_of_day(timestamp),
+        }
+    }
+}
+
+pub struct StreamOptions {
+    pub width: f64,
+    pub height: f64,
+    pub step: Step,
+}
+
+// Time per top-level category in every bucket that has any tracked time.
+fn buckets(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    step: Step,
+) -> BTreeMap<u64, HashMap<String, f64>> {
+    let (_, _, activities) = load_activities(start_timestamp, end_timestamp);
+
+    let mut buckets: BTreeMap<u64, HashMap<String, f64>> = BTreeMap::new();
+    for (start, duration, name) in split_sessions(&activities, |x| step.next(x)) {
+        let category = name.split('.').next().unwrap().to_string();
+        *buckets
+            .entry(step.start(start))
+            .or_default()
+            .entry(category)
+            .or_insert(0.) += duration as f64;
+    }
+
+    buckets
+}
+
+// Joins the points with curves that leave and enter each point horizontally,
+// like the Sankey ribbons, so the shape never overshoots the data.
+fn smooth(mut data: PathData, points: &[(f64, f64)]) -> PathData {
+    for pair in points.windows(2) {
+        let (x0, y0) = pair[0];
+        let (x1, y1) = pair[1];
+        let mx = (x0 + x1) / 2.;
+        data = data.curve_to((mx, y0), (mx, y1), x1, y1);
+    }
+
+    data
+}
+
+// Stacks each category's share of the tracked time in every bucket, in name
+// order to match the timeline. Ideal proportions are drawn as dashed lines at the
+// boundaries where each category would end if the ideal were met.
+pub fn render_streamgraph(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    ideal_proportions: Option<&HashMap<String, f64>>,
+    tags: &Registry,
+    theme: &Theme,
+    options: &StreamOptions,
+) -> String {
+    let width = options.width;
+    let step = options.step;
+    let buckets = buckets(start_timestamp, end_timestamp, step);
+
+    let mut categories: Vec<String> = buckets
+        .values()
+        .flat_map(|x| x.keys().cloned())
+        .chain(
+            ideal_proportions
+                .into_iter()
+                .flat_map(|x| x.keys().cloned())
+                .filter(|x| x != "slop"),
+        )
+        .collect();
+    categories.sort();
+    categories.dedup();
+
+    let margin = 10.;
+    let plot_height = options.height - 2. * margin;
+    let range = (end_timestamp - start_timestamp).max(1) as f64;
+    let x = |timestamp: u64| {
+        let middle = timestamp + (step.next(timestamp) - timestamp) / 2;
+        margin + (middle.saturating_sub(start_timestamp)) as f64 / range * (width - 2. * margin)
+    };
+
+    // The cumulative share at the top of each layer, per bucket.
+    let mut boundaries: Vec<Vec<(f64, f64)>> = vec![Vec::new(); categories.len() + 1];
+    for (timestamp, totals) in &buckets {
+        let total: f64 = totals.values().sum();
+        let mut share = 0.;
+        boundaries[0].push((x(*timestamp), margin));
+        for (i, category) in categories.iter().enumerate() {
+            share += totals.get(category).copied().unwrap_or(0.) / total;
+            boundaries[i + 1].push((x(*timestamp), margin + share * plot_height));
+        }
+    }
+
+    // The first and last buckets are held out to the edges.
+    for boundary in &mut boundaries {
+        if let (Some(first), Some(last)) = (boundary.first().copied(), boundary.last().copied()) {
+            boundary.insert(0, (margin, first.1));
+            boundary.push((width - margin, last.1));
+        }
+    }
+
+    let grand_total: f64 = buckets.values().flat_map(|x| x.values()).sum();
+
+    let mut svg = svg();
+    svg.push(theme.background());
+
+    if buckets.is_empty() {
+        return svg.render();
+    }
+
+    for (i, category) in categories.iter().enumerate() {
+        let top = &boundaries[i];
+        let bottom: Vec<(f64, f64)> = boundaries[i + 1].iter().rev().copied().collect();
+
+        let data = smooth(PathData::new().move_to(top[0].0, top[0].1), top);
+        let data = smooth(data.line_to(bottom[0].0, bottom[0].1), &bottom).close();
+
+        let total: f64 = buckets.values().filter_map(|x| x.get(category)).sum();
+        let tooltip = format!(
+            "{} ({:.1}%)",
+            escape(&tags.name(category)),
+            100. * total / grand_total
+        );
+        svg.push(
+            path(data)
+                .attr("class", "hover-element")
+                .attr("data-tooltip", tooltip)
+                .attr("fill", tags.color(category)),
+        );
+    }
+
+    if let Some(ideal_proportions) = ideal_proportions {
+        svg.extend(draw_ideals(
+            &categories,
+            ideal_proportions,
+            width,
+            plot_height,
+            tags,
+        ));
+    }
+
+    svg.render()
+}
+
+fn draw_ideals(
+    categories: &[String],
+    ideal_proportions: &HashMap<String, f64>,
+    width: f64,
+    plot_height: f64,
+    tags: &Registry,
+) -> Vec<Element> {
+    let margin = 10.;
+    let domain: f64 = categories
+        .iter()
+        .filter_map(|x| ideal_proportions.get(x))
+        .sum();
+    if domain <= 0. {
+        return Vec::new();
+    }
+
+    let mut elements = Vec::new();
+    let mut share = 0.;
+    // The last boundary is the bottom of the chart.
+    for category in &categories[..categories.len().saturating_sub(1)] {
+        let ideal = ideal_proportions.get(category).copied().unwrap_or(0.);
+        share += ideal / domain;
+
+        let tooltip = format!(
+            "{} ideal: {:.1}%",
+            escape(&tags.name(category)),
+            100. * ideal / domain
+        );
+        let y = margin + share * plot_height;
+        elements.push(
+            path(
+                PathData::new()
+                    .move_to(margin, y)
+                    .line_to(width - margin, y),
+            )
+            .attr("class", "hover-element")
+            .attr("data-tooltip", tooltip)
+            .attr("stroke", tags.color(category))
+            .attr("stroke-width", 2)
+            .attr("stroke-dasharray", "6 4"),
+        );
+    }
+
+    elements
+}
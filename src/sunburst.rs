@@ -0,0 +1,146 @@
+use crate::layout::{sorted_children, Order, SankeyOptions};
+use crate::svg::{path, svg, text, Element, PathData};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::tree::tooltip;
+use crate::tree_node::{Merged, TreeNode};
+use std::f64::consts::PI;
+
+struct Rings<'a> {
+    x: f64,
+    y: f64,
+    thickness: f64,
+    font_size: f64,
+    total: f64,
+    merged: &'a Merged,
+    tags: &'a Registry,
+    theme: &'a Theme,
+}
+
+impl Rings<'_> {
+    fn point(&self, radius: f64, angle: f64) -> (f64, f64) {
+        (self.x + radius * angle.sin(), self.y - radius * angle.cos())
+    }
+
+    // The segment of ring `depth` between two angles measured clockwise from
+    // twelve o'clock.
+    fn segment(&self, depth: usize, start: f64, end: f64) -> PathData {
+        // A full circle cannot be drawn as one arc.
+        let end = end.min(start + 2. * PI - 1e-6);
+        let inner = self.thickness * depth as f64;
+        let outer = inner + self.thickness;
+        let large = end - start > PI;
+
+        let (x1, y1) = self.point(outer, start);
+        let (x2, y2) = self.point(outer, end);
+        let (x3, y3) = self.point(inner, end);
+        let (x4, y4) = self.point(inner, start);
+
+        PathData::new()
+            .move_to(x1, y1)
+            .arc_to(outer, large, true, x2, y2)
+            .line_to(x3, y3)
+            .arc_to(inner, large, false, x4, y4)
+            .close()
+    }
+
+    fn draw(
+        &self,
+        node: &TreeNode,
+        label: &str,
+        depth: usize,
+        start: f64,
+        order: Order,
+    ) -> Vec<Element> {
+        let mut elements = Vec::new();
+        let mut angle = start;
+        for (key, child) in sorted_children(node, order) {
+            let label = match label.is_empty() {
+                true => key.clone(),
+                false => format!("{label}.{key}"),
+            };
+            let span = match self.total > 0. {
+                true => child.value / self.total * 2. * PI,
+                false => 0.,
+            };
+
+            elements.push(
+                path(self.segment(depth, angle, angle + span))
+                    .attr("class", "hover-element")
+                    .attr(
+                        "data-tooltip",
+                        tooltip(self.tags, self.merged, &label, child.value, self.total),
+                    )
+                    .attr("fill", self.tags.color(&label))
+                    .attr("stroke", &self.theme.background),
+            );
+
+            // Labels go at the middle of the segment when both the arc and the
+            // ring are wide enough to hold them.
+            let name = match self.tags.name(&label) {
+                x if x == label => key.clone(),
+                x => x,
+            };
+            let radius = self.thickness * (depth as f64 + 0.5);
+            let width = 0.6 * self.font_size * name.chars().count() as f64;
+            if span * radius > width && self.thickness > width {
+                let (x, y) = self.point(radius, angle + span / 2.);
+                elements.push(
+                    text(x, y + self.font_size / 4., &name)
+                        .attr("text-anchor", "middle")
+                        .attr("font-family", &self.theme.font_family)
+                        .attr("font-size", self.font_size)
+                        .attr("fill", &self.theme.text),
+                );
+            }
+
+            elements.extend(self.draw(child, &label, depth + 1, angle, order));
+            angle += span;
+        }
+
+        elements
+    }
+}
+
+// Draws the tree as concentric rings around an empty center, one ring per
+// level, with each node's angle proportional to its value.
+pub fn render_sunburst(
+    tree: &TreeNode,
+    tags: &Registry,
+    theme: &Theme,
+    options: &SankeyOptions,
+) -> String {
+    let (tree, merged) = tree.collapse(options.threshold);
+
+    let mut categories: Vec<String> = tree.children.keys().cloned().collect();
+    categories.sort();
+
+    let chrome = &options.chrome;
+    let area = chrome.area(options.width, options.height, &categories, false, false);
+
+    let radius = (area.width.min(area.height) / 2. - 10.).max(0.);
+    let rings = Rings {
+        x: area.x + area.width / 2.,
+        y: area.y + area.height / 2.,
+        thickness: radius / 4.,
+        font_size: 1.2 * options.height / 100.,
+        total: tree.value,
+        merged: &merged,
+        tags,
+        theme,
+    };
+
+    let mut svg = svg();
+    svg.push(theme.background());
+    svg.extend(rings.draw(&tree, "", 1, 0., options.order));
+    svg.extend(chrome.draw(
+        "Time by activity",
+        &categories,
+        options.width,
+        options.height,
+        tags,
+        theme,
+    ));
+
+    svg.render()
+}
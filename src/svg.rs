@@ -0,0 +1,194 @@
+use crate::util::escape;
+use std::fmt::Display;
+
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+// A minimal SVG element tree. Attribute values and text are escaped when the
+// tree is serialized, so callers never deal with markup directly.
+pub struct Element {
+    name: &'static str,
+    attributes: Vec<(&'static str, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            attributes: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    // Setting an attribute twice replaces the earlier value, which keeps the
+    // output well-formed when a caller overrides a default.
+    pub fn attr(mut self, name: &'static str, value: impl Display) -> Self {
+        match self.attributes.iter_mut().find(|x| x.0 == name) {
+            Some(x) => x.1 = value.to_string(),
+            None => self.attributes.push((name, value.to_string())),
+        }
+        self
+    }
+
+    pub fn child(mut self, child: Element) -> Self {
+        self.push(child);
+        self
+    }
+
+    pub fn text(mut self, text: &str) -> Self {
+        self.children.push(Node::Text(text.to_string()));
+        self
+    }
+
+    pub fn push(&mut self, child: Element) {
+        self.children.push(Node::Element(child));
+    }
+
+    pub fn extend(&mut self, children: Vec<Element>) {
+        for child in children {
+            self.push(child);
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        *out += "<";
+        *out += self.name;
+        for (name, value) in &self.attributes {
+            *out += format!(" {}='{}'", name, escape(value)).as_str();
+        }
+
+        if self.children.is_empty() {
+            *out += " />\n";
+            return;
+        }
+
+        *out += ">";
+        for child in &self.children {
+            match child {
+                Node::Element(x) => {
+                    if !out.ends_with('\n') {
+                        *out += "\n";
+                    }
+                    x.write(out);
+                }
+                Node::Text(x) => *out += escape(x).as_str(),
+            }
+        }
+        *out += format!("</{}>\n", self.name).as_str();
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out);
+        out
+    }
+}
+
+pub fn svg() -> Element {
+    Element::new("svg")
+        .attr("width", "100%")
+        .attr("height", "100%")
+        .attr("xmlns", "http://www.w3.org/2000/svg")
+}
+
+pub fn group() -> Element {
+    Element::new("g")
+}
+
+pub fn rect(x: f64, y: f64, width: f64, height: f64) -> Element {
+    Element::new("rect")
+        .attr("x", x)
+        .attr("y", y)
+        .attr("width", width)
+        .attr("height", height)
+}
+
+pub fn path(data: PathData) -> Element {
+    Element::new("path").attr("d", data.0)
+}
+
+pub fn text(x: f64, y: f64, content: &str) -> Element {
+    Element::new("text").attr("x", x).attr("y", y).text(content)
+}
+
+pub struct PathData(String);
+
+impl PathData {
+    pub fn new() -> Self {
+        Self(String::new())
+    }
+
+    pub fn move_to(mut self, x: f64, y: f64) -> Self {
+        self.0 += format!("M {} {}", x, y).as_str();
+        self
+    }
+
+    pub fn line_to(mut self, x: f64, y: f64) -> Self {
+        self.0 += format!("L {} {}", x, y).as_str();
+        self
+    }
+
+    pub fn curve_to(mut self, c1: (f64, f64), c2: (f64, f64), x: f64, y: f64) -> Self {
+        self.0 += format!("C {} {}, {} {}, {} {}", c1.0, c1.1, c2.0, c2.1, x, y).as_str();
+        self
+    }
+
+    pub fn arc_to(mut self, radius: f64, large: bool, sweep: bool, x: f64, y: f64) -> Self {
+        self.0 += format!(
+            "A {} {} 0 {} {} {} {}",
+            radius, radius, large as u8, sweep as u8, x, y
+        )
+        .as_str();
+        self
+    }
+
+    pub fn close(mut self) -> Self {
+        self.0 += "Z";
+        self
+    }
+}
+
+impl Default for PathData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAG: &str = "work.o'neil.\"x\"<b>&co";
+
+    // What the browser does once when reading an attribute value.
+    fn decode(text: &str) -> String {
+        text.replace("&#39;", "'")
+            .replace("&quot;", "\"")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&")
+    }
+
+    #[test]
+    fn tooltips_stay_escaped_after_the_attribute_is_decoded() {
+        let tooltip = format!("{}<br>1:00:00", escape(TAG));
+        let out = rect(0., 0., 1., 1.).attr("data-tooltip", &tooltip).render();
+
+        let start = out.find("data-tooltip='").unwrap() + "data-tooltip='".len();
+        let value = &out[start..start + out[start..].find('\'').unwrap()];
+        assert!(!value.contains(['<', '"']));
+        assert_eq!(decode(value), tooltip);
+        assert!(decode(value).contains(&escape(TAG)));
+    }
+
+    #[test]
+    fn text_content_is_escaped() {
+        let out = text(0., 0., TAG).render();
+
+        assert!(out.contains(&format!(">{}</text>", escape(TAG))));
+        assert!(!out.contains("<b>"));
+    }
+}
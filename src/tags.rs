@@ -0,0 +1,133 @@
+use crate::palette::{shade, Palette};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+pub struct TagInfo {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub description: Option<String>,
+}
+
+pub struct Registry {
+    tags: HashMap<String, TagInfo>,
+    palette: Palette,
+    order: Vec<String>,
+}
+
+fn field(value: Option<&str>) -> Option<String> {
+    match value {
+        None | Some("") | Some("-") => None,
+        Some(x) => Some(x.to_string()),
+    }
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self {
+            tags: HashMap::new(),
+            palette: Palette::Categorical,
+            order: Vec::new(),
+        }
+    }
+
+    // Top-level categories from the tag file and the given list are assigned
+    // palette entries in alphabetical order.
+    pub fn set_palette(&mut self, palette: Palette, categories: Vec<String>) {
+        let mut order: Vec<String> = self
+            .tags
+            .keys()
+            .filter(|x| !x.contains('.'))
+            .cloned()
+            .chain(categories)
+            .filter(|x| x != "slop")
+            .collect();
+        order.sort();
+        order.dedup();
+
+        self.palette = palette;
+        self.order = order;
+    }
+
+    // The tag file holds one tab separated "<tag> <color> <name> <description>"
+    // line per tag or prefix. Trailing fields may be omitted and "-" leaves a
+    // field unset.
+    pub fn load(filename: &str) -> Self {
+        let mut registry = Self::new();
+        if !Path::new(filename).exists() {
+            return registry;
+        }
+
+        let mut file = File::open(filename).unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+
+        for line in contents.lines() {
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let mut split = line.split('\t');
+            let tag = split.next().unwrap().to_string();
+            registry.tags.insert(
+                tag,
+                TagInfo {
+                    color: field(split.next()),
+                    name: field(split.next()),
+                    description: field(split.next()),
+                },
+            );
+        }
+
+        registry
+    }
+
+    // Walks from the full tag up through its prefixes and returns the first
+    // entry that has a color set, along with the path below that entry.
+    fn lookup_color<'a>(&self, tag: &'a str) -> Option<(String, Vec<&'a str>)> {
+        let parts: Vec<&str> = tag.split('.').collect();
+        for depth in (1..=parts.len()).rev() {
+            let prefix = parts[..depth].join(".");
+            if let Some(color) = self.tags.get(&prefix).and_then(|x| x.color.clone()) {
+                return Some((color, parts[depth..].to_vec()));
+            }
+        }
+
+        None
+    }
+
+    pub fn name(&self, tag: &str) -> String {
+        match self.tags.get(tag).and_then(|x| x.name.clone()) {
+            Some(x) => x,
+            None => tag.to_string(),
+        }
+    }
+
+    pub fn description(&self, tag: &str) -> Option<String> {
+        self.tags.get(tag).and_then(|x| x.description.clone())
+    }
+
+    // Colors set in the tag file take precedence and otherwise the top-level
+    // category gets its palette color. Children are shades of that color.
+    pub fn color(&self, tag: &str) -> String {
+        if let Some((color, path)) = self.lookup_color(tag) {
+            return shade(&color, &path);
+        }
+
+        let parts: Vec<&str> = tag.split('.').collect();
+        let color = self.palette.color(parts[0], &self.order);
+
+        shade(&color, &parts[1..])
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
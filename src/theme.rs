@@ -0,0 +1,51 @@
+use crate::svg::Element;
+
+pub struct Theme {
+    pub background: String,
+    pub text: String,
+    pub body: String,
+    pub highlight: String,
+    pub font_family: String,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            background: "#080d11".to_string(),
+            text: "#eee".to_string(),
+            body: "#444".to_string(),
+            highlight: "#222".to_string(),
+            font_family: "Verdana".to_string(),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            background: "#ffffff".to_string(),
+            text: "#222".to_string(),
+            body: "#ccc".to_string(),
+            highlight: "#999".to_string(),
+            font_family: "Verdana".to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "light" => Self::light(),
+            _ => Self::dark(),
+        }
+    }
+
+    pub fn background(&self) -> Element {
+        Element::new("rect")
+            .attr("width", "100%")
+            .attr("height", "100%")
+            .attr("fill", &self.background)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
@@ -1,108 +1,325 @@
-use crate::parse_file;
+use crate::chrome::{bottom_axis, percent_axis, Chrome};
+use crate::parse::{build_tree, Activity};
 use crate::render::get_points;
-use chrono::{DateTime, TimeZone};
-use chrono_tz::America::Chicago;
-use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
-use std::hash::Hash;
-use std::hash::Hasher;
+use crate::sources::load_activities;
+use crate::svg::{group, rect, svg, text};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::tree_node::TreeNode;
+use crate::util::{local, midnight, split_sessions};
+use chrono::{Datelike, Days, Months};
+use std::collections::{BTreeMap, HashMap};
 
 struct Row {
     key: String,
     delta: f64,
 }
 
-pub fn draw_timeline(
-    ideal_proportions: &HashMap<String, f64>,
-    filename: &str,
-    width: f64,
-    height: f64,
-) -> String {
-    let current_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+#[derive(Clone, Copy)]
+pub enum Granularity {
+    Day,
+    Week,
+    Month,
+}
 
-    let start_of_first_day = 1672552800;
-    let mut current_day = start_of_first_day;
+impl Granularity {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "week" => Granularity::Week,
+            "month" => Granularity::Month,
+            _ => Granularity::Day,
+        }
+    }
 
-    let mut data: Vec<(Vec<Row>, u64, f64)> = Vec::new();
-    loop {
-        let (tree, _, _) = parse_file(filename, current_day, current_day + 60 * 60 * 24);
+    // Start of the local period containing the timestamp. Weeks start on
+    // Monday.
+    pub fn start(&self, timestamp: u64) -> u64 {
+        let date = local(timestamp).date_naive();
+        match self {
+            Granularity::Day => midnight(date),
+            Granularity::Week => {
+                midnight(date - Days::new(date.weekday().num_days_from_monday() as u64))
+            }
+            Granularity::Month => midnight(date.with_day(1).unwrap()),
+        }
+    }
 
-        if tree.children.len() == 0 {
-            current_day += 60 * 60 * 24;
-            continue;
+    pub fn next(&self, timestamp: u64) -> u64 {
+        let date = local(self.start(timestamp)).date_naive();
+        match self {
+            Granularity::Day => midnight(date + Days::new(1)),
+            Granularity::Week => midnight(date + Days::new(7)),
+            Granularity::Month => midnight(date + Months::new(1)),
         }
+    }
+
+    fn format(&self) -> &'static str {
+        match self {
+            Granularity::Month => "%Y-%m",
+            _ => "%m/%d",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Granularity::Day => "Daily balance",
+            Granularity::Week => "Weekly balance",
+            Granularity::Month => "Monthly balance",
+        }
+    }
+}
+
+pub struct TimelineOptions {
+    pub width: f64,
+    pub height: f64,
+    pub start: u64,
+    pub end: u64,
+    pub granularity: Granularity,
+    // Fixed width of each column. Without it the columns share the width.
+    pub column_width: Option<f64>,
+}
+
+// The activity tree of every period from the one containing the start up to
+// the end, keyed by the start and end of the period. The last period is cut
+// off at the end, so a range ending now includes today so far, and periods
+// without tracked time have an empty tree. The range is loaded once and the
+// sessions are cut at period boundaries.
+pub fn aggregate(
+    start_timestamp: u64,
+    end_timestamp: u64,
+    granularity: Granularity,
+) -> Vec<(u64, u64, TreeNode)> {
+    let first = granularity.start(start_timestamp);
+    let (_, _, activities) = load_activities(first, end_timestamp);
+
+    periods(&activities, first, end_timestamp, granularity)
+}
+
+// Walks the periods from the one containing the start to the end and builds
+// the tree of the sessions in each.
+fn periods(
+    activities: &[Activity],
+    start_timestamp: u64,
+    end_timestamp: u64,
+    granularity: Granularity,
+) -> Vec<(u64, u64, TreeNode)> {
+    let mut buckets: BTreeMap<u64, Vec<Activity>> = BTreeMap::new();
+    for activity in split_sessions(activities, |x| granularity.next(x)) {
+        buckets
+            .entry(granularity.start(activity.0))
+            .or_default()
+            .push(activity);
+    }
+
+    let mut periods = Vec::new();
+    let mut current = granularity.start(start_timestamp);
+    while current < end_timestamp {
+        let next = granularity.next(current).min(end_timestamp);
+        let tree = match buckets.get(&current) {
+            Some(x) => build_tree(x),
+            None => TreeNode::new(),
+        };
+        periods.push((current, next, tree));
+
+        current = next;
+    }
+
+    periods
+}
+
+pub fn draw_timeline(
+    ideal_proportions: &HashMap<String, f64>,
+    tags: &Registry,
+    theme: &Theme,
+    chrome: &Chrome,
+    options: &TimelineOptions,
+) -> String {
+    let height = options.height;
 
-        let mut keys: Vec<&String> = tree.children.keys().into_iter().collect();
+    // Empty periods keep their column, without points.
+    let mut data: Vec<(Vec<Row>, u64, Option<f64>)> = Vec::new();
+    for (start, _, tree) in aggregate(options.start, options.end, options.granularity) {
+        let mut keys: Vec<&String> = tree.children.keys().collect();
         keys.sort();
 
-        let sum = keys
-            .clone()
-            .into_iter()
-            .map(|key| tree.children[key].value)
-            .sum::<f64>();
+        let proportions = tree.normalize();
 
         data.push((
             keys.into_iter()
                 .map(|key| Row {
                     key: key.clone(),
-                    delta: tree.children[key].value / sum,
+                    delta: proportions.value_at(key),
                 })
                 .collect(),
-            current_day,
-            get_points(&tree, ideal_proportions),
+            start,
+            match tree.children.is_empty() {
+                true => None,
+                false => Some(get_points(&tree, ideal_proportions)),
+            },
         ));
+    }
 
-        current_day += 60 * 60 * 24;
+    let mut categories: Vec<String> = data
+        .iter()
+        .flat_map(|x| x.0.iter().map(|row| row.key.clone()))
+        .collect();
+    categories.sort();
+    categories.dedup();
 
-        if current_day + 60 * 60 * 24 > current_time {
-            break;
-        }
-    }
+    // With a fixed column width the chart grows to fit the columns.
+    let width = match options.column_width {
+        Some(x) => chrome.area(0., height, &categories, true, false).x + x * data.len() as f64,
+        None => options.width,
+    };
 
-    let saturation = "30%";
-    let lightness = "50%";
+    let area = chrome.area(width, height, &categories, true, true);
 
-    let mut x = 0.;
-    let x_step = width / data.len() as f64;
+    let mut x = area.x;
+    let x_step = area.width / data.len() as f64;
 
-    let mut svg = format!(
-        "<svg id=timeline width='100%' height='{height}' xmlns='http://www.w3.org/2000/svg'>\n"
-    );
+    // Dates under every column that has room for one.
+    let every = ((60. / x_step).ceil() as usize).max(1);
+    let ticks: Vec<(f64, String)> = data
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| i % every == 0)
+        .map(|(i, column)| {
+            (
+                area.x + (i as f64 + 0.5) * x_step,
+                local(column.1)
+                    .format(options.granularity.format())
+                    .to_string(),
+            )
+        })
+        .collect();
+
+    let mut svg = svg()
+        .attr("id", "timeline")
+        .attr("width", width)
+        .attr("height", height);
+    svg.push(theme.background());
     for column in data {
-        let mut y = 0.;
+        let mut y = area.y;
 
         let timestamp = column.1;
 
-        let points = format!("{:.3} points", column.2);
+        let summary = match column.2 {
+            Some(points) => format!("{:.3} points", points),
+            None => "no data".to_string(),
+        };
 
-        let time: DateTime<_> = Chicago.timestamp_opt(timestamp as i64, 0).unwrap();
-        svg += format!(
-            "<g class='hover-element' data-tooltip='{time}<br>{points}' onclick='changegraph({timestamp});'>\n"
-        )
-        .as_str();
-        for row in column.0 {
-            let mut state = DefaultHasher::new();
-            row.key.hash(&mut state);
-            let hue = state.finish() % 360;
+        // The page shows the day before the one it is given, so this selects
+        // a range ending with the column.
+        let last_day = options.granularity.next(timestamp) - 60 * 60 * 24;
 
-            let delta = row.delta * height;
-            svg += format!("<rect x='{x}' y='{y}' width='{x_step}' height='{delta}' fill='hsl({hue}, {saturation}, {lightness})' />\n").as_str();
+        let time = local(timestamp);
+        let mut g = group()
+            .attr("class", "hover-element")
+            .attr("data-tooltip", format!("{time}<br>{summary}"))
+            .attr("onclick", format!("changegraph({last_day});"));
+
+        // Keeps empty columns hoverable.
+        g.push(rect(x, area.y, x_step, area.height).attr("fill", &theme.background));
+        for row in column.0 {
+            let delta = row.delta * area.height;
+            g.push(rect(x, y, x_step, delta).attr("fill", tags.color(&row.key)));
             y += delta;
         }
 
-        svg += format!(
-            "<text fill=\"white\" font-size=\"12\" x=\"{}\" y=\"{}\">{:.0}</text>",
-            x, y, column.2
-        )
-        .as_str();
+        if let Some(points) = column.2 {
+            g.push(
+                text(x, y, format!("{:.0}", points).as_str())
+                    .attr("fill", &theme.text)
+                    .attr("font-family", &theme.font_family)
+                    .attr("font-size", 12),
+            );
+        }
 
-        svg += format!("</g>").as_str();
+        svg.push(g);
         x += x_step;
     }
-    svg += "<svg><br>\n";
 
-    svg
+    if chrome.axes {
+        svg.push(percent_axis(&area, theme));
+        svg.push(bottom_axis(&ticks, &area, theme));
+    }
+    svg.extend(chrome.draw(
+        options.granularity.title(),
+        &categories,
+        width,
+        height,
+        tags,
+        theme,
+    ));
+
+    svg.render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Timelike, Weekday};
+
+    fn day(year: i32, month: u32, day: u32) -> u64 {
+        midnight(NaiveDate::from_ymd_opt(year, month, day).unwrap())
+    }
+
+    #[test]
+    fn days_after_the_last_session_are_empty_and_today_is_cut_at_the_end() {
+        let activities = [(day(2026, 10, 12) + 3600, 7200, "work.code.rust".to_string())];
+        let end = day(2026, 10, 15) + 12 * 3600;
+
+        let periods = periods(&activities, day(2026, 10, 12), end, Granularity::Day);
+
+        let bounds: Vec<(u64, u64)> = periods.iter().map(|x| (x.0, x.1)).collect();
+        assert_eq!(
+            bounds,
+            vec![
+                (day(2026, 10, 12), day(2026, 10, 13)),
+                (day(2026, 10, 13), day(2026, 10, 14)),
+                (day(2026, 10, 14), day(2026, 10, 15)),
+                (day(2026, 10, 15), end),
+            ]
+        );
+        assert_eq!(periods[0].2.value, 7200.);
+        assert!(periods[1..].iter().all(|x| x.2.children.is_empty()));
+    }
+
+    #[test]
+    fn weeks_start_on_monday_across_the_end_of_dst() {
+        let end = day(2026, 11, 11);
+        let periods = periods(&[], day(2026, 10, 28), end, Granularity::Week);
+
+        let starts: Vec<u64> = periods.iter().map(|x| x.0).collect();
+        assert_eq!(
+            starts,
+            vec![day(2026, 10, 26), day(2026, 11, 2), day(2026, 11, 9)]
+        );
+        for (start, _, _) in &periods {
+            assert_eq!(local(*start).weekday(), Weekday::Mon);
+            assert_eq!(local(*start).hour(), 0);
+        }
+        // The week of November 1st has an extra hour.
+        assert_eq!(periods[0].1 - periods[0].0, 7 * 86400 + 3600);
+        assert_eq!(periods[2].1, end);
+    }
+
+    #[test]
+    fn months_start_on_the_first_and_split_sessions_at_midnight() {
+        // From 23:00 on October 31st to 01:00 on November 1st.
+        let activities = [(day(2026, 11, 1) - 3600, 7200, "work.code.rust".to_string())];
+        let end = day(2026, 12, 2);
+
+        let periods = periods(&activities, day(2026, 10, 15), end, Granularity::Month);
+
+        let starts: Vec<u64> = periods.iter().map(|x| x.0).collect();
+        assert_eq!(
+            starts,
+            vec![day(2026, 10, 1), day(2026, 11, 1), day(2026, 12, 1)]
+        );
+        assert_eq!(periods[0].2.value, 3600.);
+        assert_eq!(periods[1].2.value, 3600.);
+        assert_eq!(periods[1].1 - periods[1].0, 30 * 86400 + 3600);
+        assert_eq!(periods[2].1, end);
+    }
 }
@@ -1,176 +1,165 @@
+use crate::chrome::scale_bar;
+use crate::component::{LabelMode, CHAR_WIDTH};
 use crate::component_builder::ComponentBuilder;
-use crate::tree_node::TreeNode;
-use crate::util::format_time;
-use std::collections::hash_map::DefaultHasher;
+use crate::layout::{layout, SankeyOptions};
+use crate::svg::{group, rect, svg};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::tree_node::{Merged, TreeNode};
+use crate::util::{escape, format_time};
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
+
+pub fn tooltip(tags: &Registry, merged: &Merged, tag: &str, value: f64, total: f64) -> String {
+    let mut tooltip = format!(
+        "{}: {} ({:.3}%)",
+        escape(&tags.name(tag)),
+        format_time(value as u64),
+        value / total * 100.
+    );
+
+    if let Some(description) = tags.description(tag) {
+        tooltip += format!("<br>{}", escape(&description)).as_str();
+    }
+
+    for (label, value) in merged.get(tag).into_iter().flatten() {
+        tooltip += format!(
+            "<br>{}: {}",
+            escape(&tags.name(label)),
+            format_time(*value as u64)
+        )
+        .as_str();
+    }
+
+    tooltip
+}
 
 pub fn render_tree(
     tree: &TreeNode,
-    width: f64,
-    height: f64,
     highlight: [String; 3],
     ideal_proportions: &HashMap<String, f64>,
+    tags: &Registry,
+    theme: &Theme,
+    options: &SankeyOptions,
 ) -> String {
-    let mut svg = format!("<svg width='100%' height='100%' xmlns='http://www.w3.org/2000/svg'>\n");
+    let mut svg = svg();
+    svg.push(theme.background());
 
-    let mut y = 10.;
-    let factor = 1.9 * tree.value / height;
-    let component_width = width / 3. - 5.;
-    let mut innercount = 0.;
-    let mut middlecount = 0.;
-    let mut outercount = 0.;
-    let step = 10.;
-    let font_size = 1.2 * height / 100.;
+    let margin = 10.;
+    let component_width = options.width / 3. - 5.;
+    let font_size = 1.2 * options.height / 100.;
 
-    let saturation = "30%";
-    let lightness = "50%";
+    // Outside labels go in a gutter to the right of each column's nodes, before
+    // the ribbons of the next column start.
+    let gutter = match options.labels {
+        LabelMode::Outside => (15. * CHAR_WIDTH * font_size + 10.).min(component_width / 3.),
+        _ => 0.,
+    };
 
-    let total_day_length = tree.value;
+    let (tree, merged) = tree.collapse(options.threshold);
+    let tree = &tree;
 
-    let mut keys: Vec<&String> = tree.children.keys().into_iter().collect();
-    keys.sort();
-    for key in keys {
-        let major = key;
-        let x = 10.;
+    let mut categories: Vec<String> = tree.children.keys().cloned().collect();
+    categories.sort();
 
-        let value = tree.children[key].value;
+    // The chart is laid out in the area left by the chrome and shifted down
+    // below the title.
+    let chrome = &options.chrome;
+    let area = chrome.area(options.width, options.height, &categories, false, true);
+    let mut plot = group().attr("transform", format!("translate(0, {})", area.y));
 
-        let color = match major == highlight[0].as_str() {
-            false => "#444",
-            true => "#222",
+    let total_day_length = tree.value;
+
+    // Middle of the last label placed outside a node, per column. Small nodes
+    // sit close together, so a label that would overlap the one above it is
+    // dropped and left to the tooltip.
+    let mut outside: HashMap<usize, f64> = HashMap::new();
+
+    let nodes = layout(tree, area.height, margin, options.order);
+    for node in &nodes {
+        let x = margin + (node.depth - 1) as f64 * component_width;
+        let end = x + component_width - gutter - 10.;
+
+        let mut label_mode = options.labels;
+        if label_mode == LabelMode::Outside && node.height < font_size {
+            let middle = node.top + node.height / 2.;
+            match outside.get(&node.depth) {
+                Some(last) if middle - last < font_size => label_mode = LabelMode::Hide,
+                _ => {
+                    outside.insert(node.depth, middle);
+                }
+            }
+        }
+
+        let color = match node.label == highlight[..node.depth].join(".") {
+            false => &theme.body,
+            true => &theme.highlight,
         };
 
-        let label = format!("{major}");
-        let mut state = DefaultHasher::new();
-        label.hash(&mut state);
-        let hue = state.finish() % 360;
-        svg += ComponentBuilder::new(x, y, x + component_width - 10., y + outercount)
-            .height(value / factor)
-            .color(format!("hsl({}, {saturation}, {lightness})", hue).as_str())
-            .body_color(color)
-            .right_text(label.as_str())
-            .font_size(font_size)
-            .data(
-                format!(
-                    "{label}: {} ({:.3}%)",
-                    format_time(value as u64),
-                    value / total_day_length * 100.
-                )
-                .as_str(),
-            )
-            .build()
-            .draw()
-            .as_str();
-
-        let tree = &tree.children[key];
-        let mut keys: Vec<&String> = tree.children.keys().into_iter().collect();
-        keys.sort();
-        for key in keys {
-            let minor = key;
-            let x = x + component_width;
-
-            let value = tree.children[key].value;
-
-            let color = match major == highlight[0].as_str() && minor == highlight[1].as_str() {
-                false => "#444",
-                true => "#222",
-            };
-
-            let label = format!("{major}.{minor}");
-            let mut state = DefaultHasher::new();
-            label.hash(&mut state);
-            svg += ComponentBuilder::new(
-                x,
-                y + outercount,
-                x + component_width - 10.,
-                y + middlecount,
-            )
-            .height(value / factor)
-            .color(format!("hsl({}, {saturation}, {lightness})", hue).as_str())
-            .body_color(color)
-            .right_text(label.as_str())
-            .font_size(font_size)
-            .data(
-                format!(
-                    "{label}: {} ({:.3}%)",
-                    format_time(value as u64),
-                    value / total_day_length * 100.
-                )
-                .as_str(),
-            )
-            .build()
-            .draw()
-            .as_str();
-
-            let tree = &tree.children[key];
-            let mut keys: Vec<&String> = tree.children.keys().into_iter().collect();
-            keys.sort();
-            for key in keys {
-                let activity = key;
-                let x = x + component_width;
-
-                let value = tree.children[key].value;
-
-                let color = match major == highlight[0].as_str()
-                    && minor == highlight[1].as_str()
-                    && activity == highlight[2].as_str()
-                {
-                    false => "#444",
-                    true => "#222",
-                };
-
-                let label = format!("{major}.{minor}.{activity}");
-                let mut state = DefaultHasher::new();
-                label.hash(&mut state);
-                svg += ComponentBuilder::new(
-                    x,
-                    y + middlecount,
-                    x + component_width - 10.,
-                    y + innercount,
-                )
-                .height(value / factor)
-                .color(format!("hsl({}, {saturation}, {lightness})", hue).as_str())
+        let label = &node.label;
+        plot.extend(
+            ComponentBuilder::new(x, node.source, end, node.top)
+                .height(node.height)
+                .color(tags.color(label).as_str())
+                .theme(theme)
                 .body_color(color)
-                .right_text(label.as_str())
+                .right_text(tags.name(label).as_str())
                 .font_size(font_size)
-                .data(
-                    format!(
-                        "{label}: {} ({:.3}%)",
-                        format_time(value as u64),
-                        value / total_day_length * 100.
-                    )
-                    .as_str(),
-                )
+                .label_mode(label_mode)
+                .outside_width(gutter - 10.)
+                .data(tooltip(tags, &merged, label, node.value, total_day_length).as_str())
                 .build()
-                .draw()
-                .as_str();
-                y += value / factor;
-                innercount += step;
-            }
-            middlecount += step;
-        }
-        outercount += step;
+                .draw(),
+        );
     }
 
-    let mut current = 10.;
-    let range = y - 10.;
+    let root = nodes
+        .iter()
+        .filter(|x| x.depth == 1)
+        .fold(None, |acc: Option<f64>, x| {
+            Some(acc.map_or(x.source, |y| y.min(x.source)))
+        });
+
+    let mut current = root.unwrap_or(margin);
+    let range = nodes
+        .iter()
+        .filter(|x| x.depth == 1)
+        .fold(0., |acc, x| acc + x.height);
     let domain = ideal_proportions.iter().fold(0., |acc, x| acc + x.1);
 
-    let mut keys: Vec<&String> = ideal_proportions.keys().into_iter().collect();
+    let mut keys: Vec<&String> = ideal_proportions.keys().collect();
     keys.sort();
 
     for key in keys {
         let value = ideal_proportions[key];
 
-        let mut state = DefaultHasher::new();
-        key.hash(&mut state);
-        let hue = state.finish() % 360;
         let height = value / domain * range;
-        let label = &key;
-        svg += format!("<rect x='0' y='{current}' width='10' height='{height}' class='hover-element' data-tooltip='{label} ({:.3}%)' fill='hsl({hue}, {saturation}, {lightness})' />\n", value / domain * 100.).as_str();
+        let tooltip = format!(
+            "{} ({:.3}%)",
+            escape(&tags.name(key)),
+            value / domain * 100.
+        );
+        plot.push(
+            rect(0., current, 10., height)
+                .attr("class", "hover-element")
+                .attr("data-tooltip", tooltip)
+                .attr("fill", tags.color(key)),
+        );
         current += height;
     }
 
-    svg + "</svg>\n"
+    svg.push(plot);
+
+    if chrome.axes && tree.value > 0. {
+        svg.push(scale_bar(range / tree.value, &area, theme));
+    }
+    svg.extend(chrome.draw(
+        "Time by activity",
+        &categories,
+        options.width,
+        options.height,
+        tags,
+        theme,
+    ));
+
+    svg.render()
 }
@@ -1,15 +1,184 @@
 use std::collections::HashMap;
 
+// Children smaller than this are merged into a single "other" node. A
+// percentage is taken of the whole tree rather than of the parent.
+#[derive(Clone, Copy)]
+pub enum Threshold {
+    Percent(f64),
+    Seconds(f64),
+}
+
+impl Threshold {
+    pub fn from_name(name: &str) -> Self {
+        match name.strip_suffix('%') {
+            Some(x) => Threshold::Percent(x.parse().unwrap()),
+            None => Threshold::Seconds(name.parse().unwrap()),
+        }
+    }
+}
+
+pub const OTHER: &str = "other";
+
+// Maps the label of each synthetic node to the labels and values of the nodes
+// merged into it.
+pub type Merged = HashMap<String, Vec<(String, f64)>>;
+
+fn join(prefix: &str, key: &str) -> String {
+    match prefix.is_empty() {
+        true => key.to_string(),
+        false => format!("{prefix}.{key}"),
+    }
+}
+
 pub struct TreeNode {
     pub value: f64,
     pub children: HashMap<String, TreeNode>,
 }
 
 impl TreeNode {
+    pub fn new() -> Self {
+        Self {
+            value: 0.,
+            children: HashMap::new(),
+        }
+    }
+
+    // Follows a dotted path such as "work.code" down from this node. The empty
+    // path is the node itself.
+    pub fn get(&self, path: &str) -> Option<&TreeNode> {
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        path.split('.')
+            .try_fold(self, |node, key| node.children.get(key))
+    }
+
+    pub fn value_at(&self, path: &str) -> f64 {
+        self.get(path).map_or(0., |x| x.value)
+    }
+
+    // Every node below this one in depth-first order with its dotted path.
+    // Siblings are visited by name so the order is stable.
+    pub fn walk(&self) -> Vec<(String, &TreeNode)> {
+        let mut out = Vec::new();
+        self.walk_into("", &mut out);
+        out
+    }
+
+    fn walk_into<'a>(&'a self, path: &str, out: &mut Vec<(String, &'a TreeNode)>) {
+        let mut keys: Vec<&String> = self.children.keys().collect();
+        keys.sort();
+
+        for key in keys {
+            let path = join(path, key);
+            let child = &self.children[key];
+            out.push((path.clone(), child));
+            child.walk_into(&path, out);
+        }
+    }
+
+    // Keeps the nodes the predicate accepts along with their ancestors'
+    // remaining values. A rejected node takes its whole subtree with it and
+    // its value is removed from every ancestor.
+    pub fn filter(&self, predicate: impl Fn(&str, &TreeNode) -> bool) -> TreeNode {
+        self.filter_below("", &predicate)
+    }
+
+    fn filter_below(&self, path: &str, predicate: &dyn Fn(&str, &TreeNode) -> bool) -> TreeNode {
+        let mut node = TreeNode {
+            value: self.value,
+            children: HashMap::new(),
+        };
+
+        for (key, child) in &self.children {
+            let path = join(path, key);
+            if !predicate(&path, child) {
+                node.value -= child.value;
+                continue;
+            }
+
+            let child = child.filter_below(&path, predicate);
+            node.children.insert(key.clone(), child);
+        }
+
+        // Removals deeper down shrink the children as well.
+        let removed: f64 = self
+            .children
+            .iter()
+            .filter_map(|(key, x)| node.children.get(key).map(|y| x.value - y.value))
+            .sum();
+        node.value -= removed;
+
+        node
+    }
+
+    // Drops every node smaller than the given value.
+    pub fn prune(&self, minimum: f64) -> TreeNode {
+        self.filter(|_, x| x.value >= minimum)
+    }
+
+    pub fn map_values(&self, f: impl Fn(&str, f64) -> f64) -> TreeNode {
+        self.map_below("", &f)
+    }
+
+    fn map_below(&self, path: &str, f: &dyn Fn(&str, f64) -> f64) -> TreeNode {
+        TreeNode {
+            value: f(path, self.value),
+            children: self
+                .children
+                .iter()
+                .map(|(key, x)| (key.clone(), x.map_below(&join(path, key), f)))
+                .collect(),
+        }
+    }
+
+    // Adds the two trees node by node. Nodes missing from one side count as
+    // zero.
+    pub fn merge(&self, other: &TreeNode) -> TreeNode {
+        self.combine(other, 1.)
+    }
+
+    // Subtracts the other tree node by node, which leaves negative values
+    // where the other tree is larger.
+    pub fn subtract(&self, other: &TreeNode) -> TreeNode {
+        self.combine(other, -1.)
+    }
+
+    fn combine(&self, other: &TreeNode, sign: f64) -> TreeNode {
+        let mut node = TreeNode {
+            value: self.value + sign * other.value,
+            children: HashMap::new(),
+        };
+
+        let empty = TreeNode::new();
+        for key in self.children.keys().chain(other.children.keys()) {
+            if node.children.contains_key(key) {
+                continue;
+            }
+
+            let a = self.children.get(key).unwrap_or(&empty);
+            let b = other.children.get(key).unwrap_or(&empty);
+            node.children.insert(key.clone(), a.combine(b, sign));
+        }
+
+        node
+    }
+
+    // Divides every value by the total so the root becomes 1. An empty tree
+    // stays all zeros.
+    pub fn normalize(&self) -> TreeNode {
+        let total = self.value;
+        self.map_values(|_, x| match total == 0. {
+            true => 0.,
+            false => x / total,
+        })
+    }
+
     pub fn insert1(&mut self, activity: &str, time: f64) {
         self.value += time;
         if self.children.contains_key(activity) {
-            let mut node = self.children.get_mut(activity).unwrap();
+            let node = self.children.get_mut(activity).unwrap();
             node.value += time;
         } else {
             let node = TreeNode {
@@ -26,10 +195,7 @@ impl TreeNode {
             let node = self.children.get_mut(minor).unwrap();
             node.insert1(activity, time);
         } else {
-            let mut node = TreeNode {
-                value: 0.,
-                children: HashMap::new(),
-            };
+            let mut node = TreeNode::new();
             node.insert1(activity, time);
             self.children.insert(minor.to_string(), node);
         }
@@ -41,12 +207,179 @@ impl TreeNode {
             let node = self.children.get_mut(major).unwrap();
             node.insert2(minor, activity, time);
         } else {
-            let mut node = TreeNode {
-                value: 0.,
-                children: HashMap::new(),
-            };
+            let mut node = TreeNode::new();
             node.insert2(minor, activity, time);
             self.children.insert(major.to_string(), node);
         }
     }
+
+    pub fn collapse(&self, threshold: Threshold) -> (TreeNode, Merged) {
+        let minimum = match threshold {
+            Threshold::Percent(x) => self.value * x / 100.,
+            Threshold::Seconds(x) => x,
+        };
+
+        let mut merged = HashMap::new();
+        let tree = self.collapse_below(String::new(), minimum, &mut merged);
+
+        (tree, merged)
+    }
+
+    // A single small child is left alone since merging it would only rename
+    // it. A real "other" child is folded into the synthetic one so the two
+    // never share a name.
+    fn collapse_below(&self, label: String, minimum: f64, merged: &mut Merged) -> TreeNode {
+        let small = self.children.values().filter(|x| x.value < minimum).count();
+
+        let mut node = TreeNode {
+            value: self.value,
+            children: HashMap::new(),
+        };
+        let mut other = TreeNode::new();
+        let mut list = Vec::new();
+
+        for (key, child) in &self.children {
+            let child_label = join(&label, key);
+            if small > 1 && (child.value < minimum || key == OTHER) {
+                other.value += child.value;
+                list.push((child_label, child.value));
+                continue;
+            }
+
+            node.children.insert(
+                key.clone(),
+                child.collapse_below(child_label, minimum, merged),
+            );
+        }
+
+        if !list.is_empty() {
+            list.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap().then_with(|| a.0.cmp(&b.0)));
+            merged.insert(join(&label, OTHER), list);
+            node.children.insert(OTHER.to_string(), other);
+        }
+
+        node
+    }
+}
+
+impl Default for TreeNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> TreeNode {
+        let mut tree = TreeNode::new();
+        tree.insert("work", "code", "rust", 300.);
+        tree.insert("work", "code", "review", 100.);
+        tree.insert("work", "meet", "standup", 50.);
+        tree.insert("fun", "games", "chess", 150.);
+        tree
+    }
+
+    #[test]
+    fn walk_visits_every_node_by_name() {
+        let tree = sample();
+        let paths: Vec<String> = tree.walk().into_iter().map(|x| x.0).collect();
+
+        assert_eq!(
+            paths,
+            [
+                "fun",
+                "fun.games",
+                "fun.games.chess",
+                "work",
+                "work.code",
+                "work.code.review",
+                "work.code.rust",
+                "work.meet",
+                "work.meet.standup",
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_removes_values_from_every_ancestor() {
+        let tree = sample().filter(|path, _| path != "work.code.review");
+
+        assert_eq!(tree.value, 500.);
+        assert_eq!(tree.value_at("work"), 350.);
+        assert_eq!(tree.value_at("work.code"), 300.);
+        assert!(tree.get("work.code.review").is_none());
+        assert_eq!(tree.value_at("fun"), 150.);
+    }
+
+    #[test]
+    fn prune_drops_small_subtrees() {
+        let tree = sample().prune(120.);
+
+        assert_eq!(tree.value, 450.);
+        assert!(tree.get("work.meet").is_none());
+        assert!(tree.get("work.code.review").is_none());
+        assert_eq!(tree.value_at("work.code.rust"), 300.);
+    }
+
+    #[test]
+    fn combine_counts_missing_nodes_as_zero() {
+        let mut other = TreeNode::new();
+        other.insert("work", "code", "rust", 100.);
+        other.insert("learn", "read", "paper", 60.);
+
+        let sum = sample().merge(&other);
+        assert_eq!(sum.value, 760.);
+        assert_eq!(sum.value_at("work.code.rust"), 400.);
+        assert_eq!(sum.value_at("learn.read.paper"), 60.);
+
+        let difference = sample().subtract(&other);
+        assert_eq!(difference.value, 440.);
+        assert_eq!(difference.value_at("work.code.rust"), 200.);
+        assert_eq!(difference.value_at("learn.read"), -60.);
+        assert_eq!(difference.value_at("fun.games.chess"), 150.);
+    }
+
+    #[test]
+    fn normalize_makes_the_root_one() {
+        let tree = sample().normalize();
+
+        assert_eq!(tree.value, 1.);
+        assert_eq!(tree.value_at("work"), 0.75);
+        assert_eq!(tree.value_at("fun.games.chess"), 0.25);
+        assert_eq!(TreeNode::new().normalize().value, 0.);
+    }
+
+    #[test]
+    fn collapse_merges_small_siblings_into_other() {
+        let mut tree = sample();
+        tree.insert("work", "other", "misc", 20.);
+
+        let (collapsed, merged) = tree.collapse(Threshold::Seconds(120.));
+
+        // "meet" and the real "other" are both small and share a node.
+        let work = collapsed.get("work").unwrap();
+        assert_eq!(work.children.len(), 2);
+        assert_eq!(work.value_at("other"), 70.);
+        assert_eq!(
+            merged["work.other"],
+            vec![
+                ("work.meet".to_string(), 50.),
+                ("work.other".to_string(), 20.)
+            ]
+        );
+
+        // A single small child is kept under its own name.
+        assert_eq!(collapsed.value_at("work.code.review"), 100.);
+        assert_eq!(collapsed.value, tree.value);
+    }
+
+    #[test]
+    fn collapse_by_percent_uses_the_whole_tree() {
+        let (collapsed, merged) = sample().collapse(Threshold::Percent(30.));
+
+        assert!(merged.is_empty());
+        assert_eq!(collapsed.children.len(), 2);
+    }
 }
@@ -0,0 +1,220 @@
+use crate::layout::{sorted_children, Order, SankeyOptions};
+use crate::svg::{rect, svg, text, Element};
+use crate::tags::Registry;
+use crate::theme::Theme;
+use crate::tree::tooltip;
+use crate::tree_node::{Merged, TreeNode};
+
+#[derive(Clone, Copy)]
+struct Area {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+// The worst aspect ratio in a row of areas laid along a side of the given
+// length.
+fn worst(row: &[f64], side: f64) -> f64 {
+    let sum: f64 = row.iter().sum();
+    let largest = row.iter().fold(0., |acc: f64, x| acc.max(*x));
+    let smallest = row.iter().fold(f64::MAX, |acc: f64, x| acc.min(*x));
+
+    (side * side * largest / (sum * sum)).max(sum * sum / (side * side * smallest))
+}
+
+// Squarified treemap layout (Bruls, Huizing and van Wijk). Values must be
+// sorted largest first. Returns one area per value in the same order.
+fn squarify(values: &[f64], area: Area) -> Vec<Area> {
+    let total: f64 = values.iter().sum();
+    if total <= 0. {
+        return values
+            .iter()
+            .map(|_| Area {
+                width: 0.,
+                height: 0.,
+                ..area
+            })
+            .collect();
+    }
+
+    let scale = area.width * area.height / total;
+    let sizes: Vec<f64> = values.iter().map(|x| x * scale).collect();
+
+    let mut areas = Vec::new();
+    let mut free = area;
+    let mut start = 0;
+    while start < sizes.len() {
+        let side = free.width.min(free.height);
+
+        let mut end = start + 1;
+        while end < sizes.len()
+            && worst(&sizes[start..=end], side) <= worst(&sizes[start..end], side)
+        {
+            end += 1;
+        }
+
+        let row = &sizes[start..end];
+        let sum: f64 = row.iter().sum();
+        if free.width >= free.height {
+            // A column along the left edge.
+            let width = match free.height > 0. {
+                true => sum / free.height,
+                false => 0.,
+            };
+            let mut y = free.y;
+            for size in row {
+                let height = match width > 0. {
+                    true => size / width,
+                    false => 0.,
+                };
+                areas.push(Area {
+                    x: free.x,
+                    y,
+                    width,
+                    height,
+                });
+                y += height;
+            }
+            free.x += width;
+            free.width -= width;
+        } else {
+            // A row along the top edge.
+            let height = match free.width > 0. {
+                true => sum / free.width,
+                false => 0.,
+            };
+            let mut x = free.x;
+            for size in row {
+                let width = match height > 0. {
+                    true => size / height,
+                    false => 0.,
+                };
+                areas.push(Area {
+                    x,
+                    y: free.y,
+                    width,
+                    height,
+                });
+                x += width;
+            }
+            free.y += height;
+            free.height -= height;
+        }
+
+        start = end;
+    }
+
+    areas
+}
+
+struct Treemap<'a> {
+    font_size: f64,
+    total: f64,
+    merged: &'a Merged,
+    tags: &'a Registry,
+    theme: &'a Theme,
+}
+
+impl Treemap<'_> {
+    fn draw(&self, node: &TreeNode, label: &str, area: Area) -> Vec<Element> {
+        let padding = 2.;
+        let children = sorted_children(node, Order::Value);
+        let values: Vec<f64> = children.iter().map(|x| x.1.value).collect();
+
+        let mut elements = Vec::new();
+        for ((key, child), area) in children.iter().zip(squarify(&values, area)) {
+            let label = match label.is_empty() {
+                true => key.to_string(),
+                false => format!("{label}.{key}"),
+            };
+
+            elements.push(
+                rect(area.x, area.y, area.width, area.height)
+                    .attr("class", "hover-element")
+                    .attr(
+                        "data-tooltip",
+                        tooltip(self.tags, self.merged, &label, child.value, self.total),
+                    )
+                    .attr("fill", self.tags.color(&label))
+                    .attr("stroke", &self.theme.background),
+            );
+
+            let name = match self.tags.name(&label) {
+                x if x == label => key.to_string(),
+                x => x,
+            };
+            let header = self.font_size + 2. * padding;
+            let fits = area.height > header
+                && area.width > 0.6 * self.font_size * name.chars().count() as f64 + 2. * padding;
+            if fits {
+                elements.push(
+                    text(area.x + padding, area.y + padding + self.font_size, &name)
+                        .attr("font-family", &self.theme.font_family)
+                        .attr("font-size", self.font_size)
+                        .attr("fill", &self.theme.text),
+                );
+            }
+
+            // Children are inset and leave room for the parent's label.
+            let top = match fits && area.height > 3. * header {
+                true => header,
+                false => padding,
+            };
+            let inner = Area {
+                x: area.x + padding,
+                y: area.y + top,
+                width: (area.width - 2. * padding).max(0.),
+                height: (area.height - top - padding).max(0.),
+            };
+            elements.extend(self.draw(child, &label, inner));
+        }
+
+        elements
+    }
+}
+
+// Draws the tree as nested rectangles with areas proportional to value.
+pub fn render_treemap(
+    tree: &TreeNode,
+    tags: &Registry,
+    theme: &Theme,
+    options: &SankeyOptions,
+) -> String {
+    let (tree, merged) = tree.collapse(options.threshold);
+
+    let mut categories: Vec<String> = tree.children.keys().cloned().collect();
+    categories.sort();
+
+    let chrome = &options.chrome;
+    let free = chrome.area(options.width, options.height, &categories, false, false);
+
+    let treemap = Treemap {
+        font_size: 1.2 * options.height / 100.,
+        total: tree.value,
+        merged: &merged,
+        tags,
+        theme,
+    };
+
+    let area = Area {
+        x: free.x + 10.,
+        y: free.y + 10.,
+        width: (free.width - 20.).max(0.),
+        height: (free.height - 20.).max(0.),
+    };
+
+    let mut svg = svg();
+    svg.push(theme.background());
+    svg.extend(treemap.draw(&tree, "", area));
+    svg.extend(chrome.draw(
+        "Time by activity",
+        &categories,
+        options.width,
+        options.height,
+        tags,
+        theme,
+    ));
+
+    svg.render()
+}
@@ -1,3 +1,8 @@
+use crate::parse::Activity;
+use chrono::{DateTime, Days, NaiveDate, TimeZone};
+use chrono_tz::America::Chicago;
+use chrono_tz::Tz;
+
 pub fn format_time(timestamp: u64) -> String {
     let hours = timestamp / 3600;
     let minutes = (timestamp % 3600) / 60;
@@ -5,3 +10,75 @@ pub fn format_time(timestamp: u64) -> String {
 
     format!("{}:{:02}:{:02}", hours, minutes, seconds)
 }
+
+// Makes text safe to place in an element or a quoted attribute. Tooltips are
+// HTML that the page inserts with innerHTML, so user text inside them has to
+// be escaped once for the tooltip and the whole tooltip again for the
+// attribute.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped += "&amp;",
+            '<' => escaped += "&lt;",
+            '>' => escaped += "&gt;",
+            '"' => escaped += "&quot;",
+            '\'' => escaped += "&#39;",
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+pub fn local(timestamp: u64) -> DateTime<Tz> {
+    Chicago.timestamp_opt(timestamp as i64, 0).unwrap()
+}
+
+pub fn midnight(date: NaiveDate) -> u64 {
+    Chicago
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        .earliest()
+        .unwrap()
+        .timestamp() as u64
+}
+
+pub fn start_of_day(timestamp: u64) -> u64 {
+    midnight(local(timestamp).date_naive())
+}
+
+// Start of the local day after the given timestamp's day.
+pub fn next_midnight(timestamp: u64) -> u64 {
+    midnight(local(timestamp).date_naive() + Days::new(1))
+}
+
+pub fn next_hour(timestamp: u64) -> u64 {
+    (timestamp / 3600 + 1) * 3600
+}
+
+// Cuts sessions wherever they cross a boundary, where `next` gives the first
+// boundary after a timestamp.
+pub fn split_sessions(activities: &[Activity], next: impl Fn(u64) -> u64) -> Vec<Activity> {
+    let mut pieces = Vec::new();
+    for (start, duration, name) in activities {
+        let end = start + *duration as u64;
+        let mut start = *start;
+        while start < end {
+            let piece_end = next(start).min(end);
+            pieces.push((start, (piece_end - start) as i64, name.clone()));
+            start = piece_end;
+        }
+    }
+
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markup_characters_are_escaped() {
+        assert_eq!(escape("a'b\"c<d>e&f"), "a&#39;b&quot;c&lt;d&gt;e&amp;f");
+    }
+}
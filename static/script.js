@@ -14,6 +14,12 @@ function getStartOfDayTimestamp() {
   return Math.floor(sod.getTime() / 1000);
 }
 
+function options() {
+  const theme = document.getElementById("theme").value;
+  document.body.className = theme;
+  return "&palette=" + document.getElementById("palette").value + "&theme=" + theme + "&order=" + document.getElementById("order").value + "&labels=" + document.getElementById("labels").value + "&chart=" + document.getElementById("chart").value + "&threshold=" + encodeURIComponent(document.getElementById("threshold").value) + "&chrome=" + (document.getElementById("chrome").checked ? "all" : "none");
+}
+
 function changegraph(time) {
   time += 60*60*24;
   a=time - period;
@@ -22,7 +28,16 @@ function changegraph(time) {
   get_timeline();
   get_sankey();
   get_stats();
+  get_bullet();
   get_band();
+  get_plan();
+  get_compare();
+  get_heatmap();
+  get_stream();
+  get_distribution();
+  get_overlaps();
+  get_aliases();
+  get_idle();
 }
 
 async function getData() {
@@ -36,7 +51,16 @@ async function getData() {
       get_timeline();
       get_sankey();
       get_stats();
+      get_bullet();
       get_band();
+      get_plan();
+      get_compare();
+      get_heatmap();
+      get_stream();
+      get_distribution();
+      get_overlaps();
+      get_aliases();
+      get_idle();
       return;
     case "1-hour":
       period = 60*60;
@@ -70,11 +94,28 @@ async function getData() {
   get_timeline();
   get_sankey();
   get_stats();
+  get_bullet();
   get_band();
+  get_plan();
+  get_compare();
+  get_heatmap();
+  get_stream();
+  get_distribution();
+  get_overlaps();
+  get_aliases();
+  get_idle();
 }
 
 async function get_timeline() {
-  const response = await fetch("/timeline?width=" + "1920" + "&height=" + "80");
+  const granularity = document.getElementById("granularity").value;
+  const column_width = document.getElementById("column-width").value;
+
+  let query = "&granularity=" + granularity;
+  if (column_width != "") {
+    query += "&column_width=" + column_width;
+  }
+
+  const response = await fetch("/timeline?width=" + "1920" + "&height=" + "80" + query + options());
   const text = await response.text();
   document.getElementById("timeline").innerHTML = text;
 
@@ -85,16 +126,27 @@ async function get_stats() {
   let start_time = a;
   let end_time = b;
 
-  const response = await fetch("/stats?start_time=" + start_time + "&end_time=" + end_time);
+  const response = await fetch("/stats?start_time=" + start_time + "&end_time=" + end_time + options());
   const text = await response.text();
   document.getElementById("stats").innerHTML = text;
 }
 
+async function get_bullet() {
+  let start_time = a;
+  let end_time = b;
+
+  const response = await fetch("/bullet?start_time=" + start_time + "&end_time=" + end_time + "&width=" + window.innerWidth + options());
+  const text = await response.text();
+  document.getElementById("bullet").innerHTML = text;
+
+  apply_hover();
+}
+
 async function get_sankey() {
   let start_time = a;
   let end_time = b;
 
-  const response = await fetch("/sankey?start_time=" + start_time + "&end_time=" + end_time + "&width=" + window.innerWidth + "&height=" + window.innerHeight);
+  const response = await fetch("/sankey?start_time=" + start_time + "&end_time=" + end_time + "&width=" + window.innerWidth + "&height=" + window.innerHeight + options());
   const text = await response.text();
   document.getElementById("sankey").innerHTML = text;
   document.getElementById("sankey").setAttribute("style",`height:${window.innerHeight}px`);
@@ -110,7 +162,7 @@ async function get_band() {
   let start_time = a;
   let end_time = b;
 
-  const response = await fetch("/band?start_time=" + start_time + "&end_time=" + end_time + "&width=" + window.innerWidth + "&height=" + window.innerHeight);
+  const response = await fetch("/band?start_time=" + start_time + "&end_time=" + end_time + "&width=" + window.innerWidth + "&height=" + window.innerHeight + "&mode=" + document.getElementById("band-mode").value + options());
   const text = await response.text();
   document.getElementById("band").innerHTML = text;
   document.getElementById("band").setAttribute("style",`height:${window.innerHeight}px`);
@@ -118,6 +170,93 @@ async function get_band() {
   apply_hover();
 }
 
+async function get_plan() {
+  let start_time = a;
+  let end_time = b;
+
+  const response = await fetch("/plan?start_time=" + start_time + "&end_time=" + end_time + "&width=" + window.innerWidth + "&height=" + window.innerHeight + options());
+  const text = await response.text();
+  document.getElementById("plan").innerHTML = text;
+  document.getElementById("plan").setAttribute("style",`height:${window.innerHeight}px`);
+
+  apply_hover();
+}
+
+async function get_compare() {
+  let start_time = a;
+  let end_time = b;
+
+  const response = await fetch("/compare?start_time=" + start_time + "&end_time=" + end_time + "&width=" + window.innerWidth + options());
+  const text = await response.text();
+  document.getElementById("compare").innerHTML = text;
+
+  apply_hover();
+}
+
+async function get_heatmap() {
+  const year = 60*60*24*365;
+  const category = document.getElementById("heatmap-category").value;
+
+  const response = await fetch("/heatmap?start_time=" + (b - year) + "&end_time=" + b + "&category=" + encodeURIComponent(category) + options());
+  const text = await response.text();
+  document.getElementById("heatmap").innerHTML = text;
+
+  apply_hover();
+}
+
+async function get_stream() {
+  let start_time = a;
+  let end_time = b;
+  const step = document.getElementById("stream-step").value;
+  const ideal = document.getElementById("stream-ideal").checked;
+
+  const response = await fetch("/stream?start_time=" + start_time + "&end_time=" + end_time + "&width=" + window.innerWidth + "&height=" + (window.innerHeight / 2) + "&step=" + step + "&ideal=" + ideal + options());
+  const text = await response.text();
+  document.getElementById("stream").innerHTML = text;
+  document.getElementById("stream").setAttribute("style",`height:${window.innerHeight / 2}px`);
+
+  apply_hover();
+}
+
+async function get_distribution() {
+  let start_time = a;
+  let end_time = b;
+  const category = document.getElementById("distribution-category").value;
+
+  const response = await fetch("/distribution?start_time=" + start_time + "&end_time=" + end_time + "&width=" + window.innerWidth + "&category=" + encodeURIComponent(category) + options());
+  const text = await response.text();
+  document.getElementById("distribution").innerHTML = text;
+
+  apply_hover();
+}
+
+async function get_overlaps() {
+  let start_time = a;
+  let end_time = b;
+
+  const response = await fetch("/overlaps?start_time=" + start_time + "&end_time=" + end_time);
+  const text = await response.text();
+  document.getElementById("overlaps").innerHTML = text;
+}
+
+async function get_aliases() {
+  let start_time = a;
+  let end_time = b;
+
+  const response = await fetch("/aliases?start_time=" + start_time + "&end_time=" + end_time);
+  const text = await response.text();
+  document.getElementById("aliases").innerHTML = text;
+}
+
+async function get_idle() {
+  let start_time = a;
+  let end_time = b;
+
+  const response = await fetch("/idle?start_time=" + start_time + "&end_time=" + end_time);
+  const text = await response.text();
+  document.getElementById("idle").innerHTML = text;
+}
+
 function apply_hover() {
   const hoverElements = document.querySelectorAll('.hover-element');
 
@@ -150,11 +289,27 @@ function apply_hover() {
 get_timeline();
 get_sankey();
 get_stats();
+get_bullet();
 get_band();
+get_plan();
+get_compare();
+get_heatmap();
+get_stream();
+get_distribution();
+get_overlaps();
+get_aliases();
+get_idle();
 let interval = setInterval(function() {
   a = getStartOfDayTimestamp();
   b = Math.floor(+(new Date())/1000);
   get_sankey();
   get_stats();
+  get_bullet();
   get_band();
+  get_plan();
+  get_compare();
+  get_stream();
+  get_distribution();
+  get_overlaps();
+  get_aliases();
 }, 10000);